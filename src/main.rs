@@ -1,4 +1,5 @@
 use std::{
+    env,
     f32::consts::PI,
     fmt, io,
     ops::{Add, Div, Mul, Neg, Sub},
@@ -6,15 +7,50 @@ use std::{
 
 const MAX_ACCELERAION: f32 = 100.0;
 const POD_RADIUS: f32 = 400.0;
+const PODS: usize = 2;
 const OPPONENTS: usize = 2;
-const FUTURE_TIME: f32 = 4.0;
 const DRAG_COEF: f32 = 0.85;
-
-macro_rules! parse_input {
-    ($x:expr, $t:ident) => {
-        $x.trim().parse::<$t>().unwrap()
-    };
-}
+const FINAL_CHECKPOINTS: usize = 1;
+const CHECKPOINT_RADIUS: f32 = 600.0;
+const MAX_ROTATION_DEG: f32 = 18.0;
+// Flight time to the checkpoint under which the racer stops blending its aim
+// and turns straight for the next one, when standing still. See
+// `Pod::switch_time` for a pod on the move.
+const FUTURE_TIME: f32 = 1.6;
+const BOOST_ACCELERATION: f32 = 650.0;
+const TIMEOUT_TURNS: u32 = 100;
+// Cosine to the checkpoint a boost is only worth spending above.
+const BOOST_ALIGNMENT: f32 = 0.99;
+const POD_MASS: f32 = 1.0;
+const SHIELD_MASS: f32 = 10.0;
+const SHIELD_COOLDOWN: u8 = 3;
+const MIN_IMPULSE: f32 = 120.0;
+const AMBUSH_DISTANCE: f32 = CHECKPOINT_RADIUS + 2.0 * POD_RADIUS;
+const ARRIVAL_TURNS: u32 = 20;
+// Aim points tried on either side of the checkpoint centre, out to a margin
+// short of its edge so small steering errors do not miss it.
+const ARRIVAL_CANDIDATES: i32 = 3;
+const ARRIVAL_SPREAD: f32 = 0.75 * CHECKPOINT_RADIUS;
+// No race is this long, and a fixed size keeps `Pod` copyable.
+const RECORDED_LAPS: usize = 8;
+const STEER_HISTORY: usize = 5;
+const OSCILLATION_REVERSALS: usize = 2;
+const DEFAULT_SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+// How soon pods have to be set to collide for my others to step in, one of
+// mine giving way or my attacker guarding my racer.
+const TEAMMATE_WARNING_TURNS: f32 = 6.0;
+// Checkpoints my racer has to be ahead by to be worth guarding.
+const GOALIE_LEAD: usize = 1;
+const MAP_WIDTH: f32 = 16000.0;
+const MAP_HEIGHT: f32 = 9000.0;
+// How far past the edge of the map a pod line may put a pod before it is
+// rejected. Collisions can push pods out a little, never this far.
+const MAP_SLACK: f32 = 2000.0;
+// Shorter vectors are rounding noise rather than a direction, and squaring
+// them can underflow. `Vec2::normalized` treats them as zero.
+const NORMALIZE_EPSILON: f32 = 1e-6;
+// How many turns ahead the debug log traces each pod's path.
+const LOGGED_PATH_TURNS: u32 = 5;
 
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
 struct Vec2 {
@@ -23,10 +59,26 @@ struct Vec2 {
 }
 
 impl Vec2 {
-    fn new(x: f32, y: f32) -> Self {
+    const fn new(x: f32, y: f32) -> Self {
         Self { x, y }
     }
 
+    /// The unit vector `angle` radians round from the x axis.
+    fn from_angle(angle: f32) -> Self {
+        Self::new(angle.cos(), angle.sin())
+    }
+
+    /// The heading in degrees as the engine reports it, from 0 up to 360
+    /// clockwise on screen starting along the x axis.
+    fn to_degrees(self) -> f32 {
+        let degrees = self.y.atan2(self.x).to_degrees();
+        if degrees < 0.0 {
+            degrees + 360.0
+        } else {
+            degrees
+        }
+    }
+
     fn rotate(self, angle: f32) -> Self {
         let cosine = angle.cos();
         let sine = angle.sin();
@@ -55,17 +107,42 @@ impl Vec2 {
         self.inner_product(self).sqrt()
     }
 
+    /// The closest point within a `width` by `height` map.
+    fn clamp_to_map(self, width: f32, height: f32) -> Self {
+        Self::new(self.x.clamp(0.0, width), self.y.clamp(0.0, height))
+    }
+
+    /// Linear interpolation, `t` of 0 giving `self` and 1 giving `other`.
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+
+    fn componentwise_min(self, other: Self) -> Self {
+        Self::new(self.x.min(other.x), self.y.min(other.y))
+    }
+
+    fn componentwise_max(self, other: Self) -> Self {
+        Self::new(self.x.max(other.x), self.y.max(other.y))
+    }
+
+    /// The unit vector in the same direction, or the zero vector when `self`
+    /// is too short for its direction to mean anything.
     fn normalized(self) -> Self {
-        self / {
-            if self.norm() == 0.0 {
-                1.0
-            } else {
-                self.norm()
-            }
+        let norm = self.norm();
+        if norm < NORMALIZE_EPSILON {
+            Self::default()
+        } else {
+            self / norm
         }
     }
 }
 
+impl fmt::Display for Vec2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({:.1}, {:.1})", self.x, self.y)
+    }
+}
+
 impl Add for Vec2 {
     type Output = Self;
     fn add(self, rhs: Self) -> Self::Output {
@@ -100,23 +177,310 @@ impl Div<f32> for Vec2 {
     }
 }
 
+/// Tunable values, defaulting to the game engine's own.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Config {
+    friction: f32,
+    map_width: f32,
+    map_height: f32,
+    // Pods on my team and on the other, the referee's input has no say in it.
+    pods: usize,
+    opponents: usize,
+    // How much of a new command is taken on when steering oscillates, 1 being
+    // no smoothing at all.
+    steer_smoothing: f32,
+    // Steering away from a map edge per unit the pod would end up past it.
+    wall_avoidance: f32,
+    racer: RacerConfig,
+    attacker: AttackerConfig,
+    // Seed for the `Rng` the `Controller` hands to `search_command`.
+    seed: u64,
+    // Smallest `Pod::threat` worth raising a shield for.
+    shield_threshold: f32,
+    // Weight of the thrust a shield gives up against the hit it avoids.
+    shield_cost: f32,
+    // Weights of `score_state`. Laps count through the checkpoints passed on
+    // them, so they need no weight of their own.
+    checkpoint_score: f32,
+    distance_score: f32,
+    attacker_score: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            friction: DRAG_COEF,
+            map_width: MAP_WIDTH,
+            map_height: MAP_HEIGHT,
+            pods: PODS,
+            opponents: OPPONENTS,
+            steer_smoothing: 0.5,
+            wall_avoidance: 1.0,
+            racer: RacerConfig::default(),
+            attacker: AttackerConfig::default(),
+            seed: DEFAULT_SEED,
+            shield_threshold: 200.0,
+            shield_cost: 0.25,
+            // Worth more than any distance on the map, so passing a checkpoint
+            // always improves the score.
+            checkpoint_score: 20000.0,
+            distance_score: 1.0,
+            attacker_score: 0.5,
+        }
+    }
+}
+
+impl Config {
+    /// Top speed at full thrust, where friction takes off as much as thrust
+    /// adds.
+    fn terminal_speed(&self) -> f32 {
+        MAX_ACCELERAION * self.friction / (1.0 - self.friction)
+    }
+
+    /// The physics these settings play out under, for tools that need to
+    /// agree with the bot on them.
+    fn physics(&self) -> Physics {
+        Physics {
+            map_width: self.map_width,
+            map_height: self.map_height,
+            pod_radius: POD_RADIUS,
+            checkpoint_radius: CHECKPOINT_RADIUS,
+            friction: self.friction,
+            max_acceleration: MAX_ACCELERAION,
+            boost_acceleration: BOOST_ACCELERATION,
+            max_rotation_deg: MAX_ROTATION_DEG,
+            shield_mass: SHIELD_MASS,
+        }
+    }
+}
+
+/// The resolved physical constants of a race, see `Config::physics`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Physics {
+    map_width: f32,
+    map_height: f32,
+    pod_radius: f32,
+    checkpoint_radius: f32,
+    friction: f32,
+    max_acceleration: f32,
+    boost_acceleration: f32,
+    max_rotation_deg: f32,
+    shield_mass: f32,
+}
+
+/// Tunable values only the racer uses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct RacerConfig {
+    // Shape of `thrust_profile`, easing off sharply into turns.
+    thrust_exponent: i32,
+    thrust_gain: f32,
+    // Plan checkpoint arrival with `Pod::arrival_target` instead of the fixed
+    // racing line offset.
+    arrival_planning: bool,
+    // Boost off the start line rather than save it for the longest leg.
+    opening_boost: bool,
+    // Alignment (cosine to the steering target) below which the racer only
+    // turns, without thrusting. The default of -1 leaves it to
+    // `thrust_profile`, which at the default exponent and gain already cuts
+    // thrust this far out of line. A gentler profile wants it around 0.5.
+    realign_alignment: f32,
+    // Shortest lookahead past a checkpoint's radius, and the fraction of the
+    // coasting distance to look ahead by on a hairpin.
+    lookahead_margin: f32,
+    lookahead_speed_factor: f32,
+    // Flight time added to `FUTURE_TIME` at terminal speed, in proportion to
+    // the speed below it, as a faster pod takes longer to come round.
+    switch_speed_factor: f32,
+}
+
+impl Default for RacerConfig {
+    fn default() -> Self {
+        Self {
+            thrust_exponent: 4,
+            thrust_gain: 16.0,
+            arrival_planning: false,
+            opening_boost: false,
+            realign_alignment: -1.0,
+            lookahead_margin: 1500.0,
+            lookahead_speed_factor: 0.5,
+            switch_speed_factor: 0.5,
+        }
+    }
+}
+
+/// Tunable values only the attacker uses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AttackerConfig {
+    // Shape of `thrust_profile`, pushing on when somewhat out of line to close
+    // distance.
+    thrust_exponent: i32,
+    thrust_gain: f32,
+    // How opponents are expected to move.
+    opponent_model: OpponentModel,
+    // Turns to spare when racing an opponent to an ambush point, and how close
+    // in turns it has to be before the attacker leaves the ambush to strike.
+    ambush_margin: f32,
+    ambush_strike_time: f32,
+    // Go for where the opponent can be met with `Pod::intercept` instead of
+    // one turn ahead of it.
+    intercept: bool,
+    // Limits within which `should_commit_ram` goes for a ram.
+    ram_turns: f32,
+    ram_closing_speed: f32,
+    // Guard my racer from opponents about to ram it while it leads.
+    goalie: bool,
+    // Most turns the attacker may need to reach an opponent for
+    // `choose_opponent` to go after it.
+    reach_turns: f32,
+}
+
+impl Default for AttackerConfig {
+    fn default() -> Self {
+        Self {
+            thrust_exponent: 2,
+            thrust_gain: 16.0,
+            opponent_model: OpponentModel::Linear,
+            ambush_margin: 3.0,
+            ambush_strike_time: 2.0,
+            intercept: false,
+            ram_turns: 3.0,
+            ram_closing_speed: 200.0,
+            goalie: false,
+            reach_turns: 10.0,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 struct RaceParameters {
     checkpoints: Vec<Vec2>,
+    // Unit vector from each checkpoint to the one after it. Checkpoints never
+    // move during a race, so these are computed once up front.
+    directions: Vec<Vec2>,
+    // Index of the checkpoint starting the longest leg, the best place to
+    // boost down.
+    longest_leg: usize,
     opponents: Vec<Pod>,
+    // My own pods as of this turn, for pods that look out for each other.
+    team: Vec<Pod>,
     laps: u8,
+    config: Config,
+    // The turn being played, counting from 1 once the first frame is in.
+    turn: u32,
 }
 
 impl RaceParameters {
-    fn new(checkpoints: Vec<Vec2>, opponents: Vec<Pod>, laps: u8) -> Self {
+    fn builder() -> RaceParametersBuilder {
+        RaceParametersBuilder::default()
+    }
+
+    fn new(checkpoints: Vec<Vec2>, opponents: Vec<Pod>, laps: u8, config: Config) -> Self {
+        let directions = checkpoints
+            .iter()
+            .zip(checkpoints.iter().cycle().skip(1))
+            .map(|(&current_cp, &next_cp)| (next_cp - current_cp).normalized())
+            .collect();
+        let longest_leg = (0..checkpoints.len())
+            .max_by(|&idx1, &idx2| {
+                let leg = |idx: usize| {
+                    (checkpoints[(idx + 1) % checkpoints.len()] - checkpoints[idx]).norm()
+                };
+                leg(idx1).total_cmp(&leg(idx2))
+            })
+            .unwrap_or(0);
         Self {
             checkpoints,
+            directions,
+            longest_leg,
             opponents,
+            team: Vec::new(),
             laps,
+            config,
+            turn: 0,
+        }
+    }
+
+    /// Index of the checkpoint the longest leg of the track starts from.
+    fn longest_leg(&self) -> usize {
+        self.longest_leg
+    }
+
+    /// Opposite corners of the smallest axis aligned box holding every
+    /// checkpoint centre, the low corner first. `None` without checkpoints.
+    fn bounds(&self) -> Option<(Vec2, Vec2)> {
+        let (&first, rest) = self.checkpoints.split_first()?;
+        Some(rest.iter().fold((first, first), |(low, high), &cp| {
+            (low.componentwise_min(cp), high.componentwise_max(cp))
+        }))
+    }
+
+    /// Where to aim for checkpoint `idx`: a pod radius past its centre towards
+    /// the next one, cut down to half the leg when the two are so close that
+    /// the full offset would land beyond the next checkpoint.
+    fn racing_line(&self, idx: usize) -> Vec2 {
+        let current_cp = self.checkpoints[idx];
+        let leg = (self.checkpoints[(idx + 1) % self.checkpoints.len()] - current_cp).norm();
+        current_cp + self.directions[idx] * POD_RADIUS.min(leg / 2.0)
+    }
+}
+
+/// Assembles a `RaceParameters` one piece at a time. Laps default to 3 and the
+/// config to `Config::default`.
+#[derive(Debug, Clone, PartialEq)]
+struct RaceParametersBuilder {
+    checkpoints: Vec<Vec2>,
+    opponents: Vec<Pod>,
+    team: Vec<Pod>,
+    laps: u8,
+    config: Config,
+}
+
+impl Default for RaceParametersBuilder {
+    fn default() -> Self {
+        Self {
+            checkpoints: Vec::new(),
+            opponents: Vec::new(),
+            team: Vec::new(),
+            laps: 3,
+            config: Config::default(),
         }
     }
 }
 
+impl RaceParametersBuilder {
+    fn checkpoint(mut self, checkpoint: Vec2) -> Self {
+        self.checkpoints.push(checkpoint);
+        self
+    }
+
+    fn opponent(mut self, pod: Pod) -> Self {
+        self.opponents.push(pod);
+        self
+    }
+
+    fn teammate(mut self, pod: Pod) -> Self {
+        self.team.push(pod);
+        self
+    }
+
+    fn laps(mut self, laps: u8) -> Self {
+        self.laps = laps;
+        self
+    }
+
+    fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    fn build(self) -> RaceParameters {
+        let mut parameters =
+            RaceParameters::new(self.checkpoints, self.opponents, self.laps, self.config);
+        parameters.team = self.team;
+        parameters
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 struct Pod {
     pos: Vec2,
@@ -126,6 +490,30 @@ struct Pod {
     checkpoint_idx: usize,
     lap: u8,
     role: Role,
+    // 10 on the turn a shield goes up, 1 otherwise.
+    mass: f32,
+    // Turns left during which a shielded pod cannot thrust, estimated by
+    // `Pod::infer_shield` for opponents.
+    shield_cooldown: u8,
+    // Only one boost per race.
+    boost_available: bool,
+    // The turn each lap was completed on.
+    lap_completions: [u32; RECORDED_LAPS],
+    // The last few steering directions and thrusts sent, as a ring buffer
+    // with `history_idx` pointing at the oldest entry.
+    steer_history: [Vec2; STEER_HISTORY],
+    thrust_history: [f32; STEER_HISTORY],
+    history_idx: usize,
+    // As of the frame before the last `update`.
+    prev: Motion,
+}
+
+/// Where a pod was and how it was moving at one point in time.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Motion {
+    pos: Vec2,
+    vel: Vec2,
+    orientation: Vec2,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -141,6 +529,16 @@ enum Action {
     Shield,
 }
 
+impl Action {
+    /// Thrust kept within the 0 to 100 the referee accepts.
+    fn clamped(self) -> Self {
+        match self {
+            Action::Accelerate(accel) => Action::Accelerate(accel.clamp(0.0, MAX_ACCELERAION)),
+            action => action,
+        }
+    }
+}
+
 impl fmt::Display for Action {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -151,6 +549,26 @@ impl fmt::Display for Action {
     }
 }
 
+impl fmt::Display for Pod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "pod[{:?}] pos={} vel={} dv={} angle={:.0} turned={:.0} cp={} lap={} \
+             lap_times={:?} thrust={:.0}",
+            self.role,
+            self.pos,
+            self.vel,
+            self.velocity_change(),
+            self.orientation.to_degrees(),
+            self.turn_rate(),
+            self.checkpoint_idx,
+            self.lap,
+            self.lap_durations(),
+            self.accel
+        )
+    }
+}
+
 impl Pod {
     fn new(
         x: f32,
@@ -161,60 +579,247 @@ impl Pod {
         checkpoint_idx: usize,
         role: Role,
     ) -> Self {
-        Self {
+        let motion = Motion {
             pos: Vec2::new(x, y),
             vel: Vec2::new(vx, vy),
+            orientation: Vec2::from_angle(orient_angle.to_radians()),
+        };
+        Self {
+            pos: motion.pos,
+            vel: motion.vel,
             accel: MAX_ACCELERAION,
-            orientation: Vec2::new(1.0, 0.0).rotate_deg(orient_angle),
+            orientation: motion.orientation,
             checkpoint_idx,
             lap: 0,
             role,
+            mass: POD_MASS,
+            shield_cooldown: 0,
+            boost_available: true,
+            lap_completions: [0; RECORDED_LAPS],
+            steer_history: [Vec2::default(); STEER_HISTORY],
+            thrust_history: [0.0; STEER_HISTORY],
+            history_idx: 0,
+            prev: motion,
         }
     }
 
+    // Pods start on checkpoint 0 and head for checkpoint 1, so that is the
+    // initial target; starting from 0 would count a lap on the first update.
     fn attacker() -> Self {
-        Self::new(0.0, 0.0, 0.0, 0.0, 0.0, 0, Role::Attacker)
+        Self::new(0.0, 0.0, 0.0, 0.0, 0.0, 1, Role::Attacker)
     }
 
     fn racer() -> Self {
-        Self::new(0.0, 0.0, 0.0, 0.0, 0.0, 0, Role::Racer)
+        Self::new(0.0, 0.0, 0.0, 0.0, 0.0, 1, Role::Racer)
     }
 
-    fn update(
-        &mut self,
-        x: f32,
-        y: f32,
-        vx: f32,
-        vy: f32,
-        orient_angle: f32,
-        checkpoint_idx: usize,
-    ) {
-        self.lap = if checkpoint_idx != self.checkpoint_idx {
-            self.lap + 1
-        } else {
-            self.lap
-        };
-        self.pos = Vec2::new(x, y);
-        self.vel = Vec2::new(vx, vy);
-        self.orientation = Vec2::new(1.0, 0.0).rotate_deg(orient_angle);
-        self.checkpoint_idx = checkpoint_idx;
+    /// Put the pod back on the starting grid at `start` for a new race, as
+    /// `Pod::new` would, without allocating a new one.
+    #[cfg(test)]
+    fn reset(&mut self, start: Vec2, role: Role) {
+        *self = Self::new(start.x, start.y, 0.0, 0.0, 0.0, 1, role);
+    }
+
+    /// Take on the state read for `turn`.
+    fn update(&mut self, frame: &PodFrame, turn: u32) {
+        // A lap is complete once checkpoint 0 is passed.
+        if self.checkpoint_idx == 0 && frame.checkpoint_idx != 0 {
+            self.complete_lap(turn);
+        }
+        self.prev = self.motion();
+        self.pos = Vec2::new(frame.x, frame.y);
+        self.vel = Vec2::new(frame.vx, frame.vy);
+        self.orientation = Vec2::from_angle(frame.orient_angle.to_radians());
+        self.checkpoint_idx = frame.checkpoint_idx;
+    }
+
+    /// Count a lap as finished on `turn`.
+    fn complete_lap(&mut self, turn: u32) {
+        if let Some(completion) = self.lap_completions.get_mut(self.lap as usize) {
+            *completion = turn;
+        }
+        self.lap += 1;
+    }
+
+    /// The turn each lap so far was completed on. Only the first
+    /// `RECORDED_LAPS` are kept, later laps still count in `lap` but go
+    /// untimed.
+    fn lap_turns(&self) -> &[u32] {
+        &self.lap_completions[..(self.lap as usize).min(RECORDED_LAPS)]
     }
 
+    /// How many turns each lap so far took.
+    fn lap_durations(&self) -> Vec<u32> {
+        let mut start = 0;
+        self.lap_turns()
+            .iter()
+            .map(|&end| {
+                let duration = end - start;
+                start = end;
+                duration
+            })
+            .collect()
+    }
+
+    /// Number of checkpoints passed since the start of the race.
+    fn checkpoints_passed(&self, checkpoint_n: usize) -> usize {
+        self.lap as usize * checkpoint_n + (self.checkpoint_idx + checkpoint_n - 1) % checkpoint_n
+    }
+
+    /// Number of checkpoints still to be passed to finish the race.
+    fn checkpoints_remaining(&self, parameters: &RaceParameters) -> usize {
+        let checkpoint_n = parameters.checkpoints.len();
+        (parameters.laps as usize * checkpoint_n)
+            .saturating_sub(self.checkpoints_passed(checkpoint_n))
+    }
+
+    /// Whether the pod is on the final lap heading for the finishing checkpoint.
+    fn on_final_checkpoint(&self, parameters: &RaceParameters) -> bool {
+        self.checkpoints_remaining(parameters) <= FINAL_CHECKPOINTS
+    }
+
+    /// Decide this turn's command and remember the chosen thrust for the next
+    /// turn's estimates.
     fn navigate(&mut self, parameters: &RaceParameters) -> (Vec2, Action) {
+        let predictor = parameters.config.attacker.opponent_model.predictor();
+        let (steer_vec, accel, action) = self.plan_thrust(parameters, predictor);
+        self.record(steer_vec, accel, action);
+        (steer_vec, action)
+    }
+
+    /// Remember a command decided elsewhere, by `search_command`, as
+    /// `navigate` would its own. A shield keeps the thrust to come back to.
+    fn follow(&mut self, steer_vec: Vec2, action: Action) {
+        let accel = match action {
+            Action::Accelerate(accel) => accel,
+            Action::Boost => MAX_ACCELERAION,
+            Action::Shield => self.accel,
+        };
+        self.record(steer_vec, accel, action);
+    }
+
+    fn record(&mut self, steer_vec: Vec2, accel: f32, action: Action) {
+        self.accel = accel;
+        if action == Action::Boost {
+            self.boost_available = false;
+        }
+        self.steer_history[self.history_idx] = (steer_vec - self.pos).normalized();
+        self.thrust_history[self.history_idx] = accel;
+        self.history_idx = (self.history_idx + 1) % STEER_HISTORY;
+    }
+
+    /// Swap the command `navigate` last recorded for `steer_vec` and `action`
+    /// when it is overruled after the fact.
+    fn overrule(&mut self, steer_vec: Vec2, action: Action) {
+        let last_idx = (self.history_idx + STEER_HISTORY - 1) % STEER_HISTORY;
+        self.steer_history[last_idx] = (steer_vec - self.pos).normalized();
+        if let Action::Accelerate(accel) = action {
+            self.accel = accel;
+            self.thrust_history[last_idx] = accel;
+        }
+    }
+
+    /// Decide this turn's command without touching the pod, so hypothetical
+    /// states can be evaluated freely.
+    fn plan(&self, parameters: &RaceParameters) -> (Vec2, Action) {
+        let predictor = parameters.config.attacker.opponent_model.predictor();
+        let (steer_vec, _, action) = self.plan_thrust(parameters, predictor);
+        (steer_vec, action)
+    }
+
+    // The thrust is returned separately because it is still wanted when the
+    // action ends up being a shield.
+    fn plan_thrust(
+        &self,
+        parameters: &RaceParameters,
+        predictor: &dyn PredictOpponent,
+    ) -> (Vec2, f32, Action) {
+        // Before the first move the reported orientation is a placeholder and
+        // the pod can turn to face anything, so head straight for the
+        // checkpoint. Unless configured otherwise the boost is saved for the
+        // longest leg, the tests show it gains more there than off the line.
+        // Only the turn number tells the opening apart: a pod can come to a
+        // standstill later on too, holding an ambush for one.
+        if parameters.turn == 1 {
+            let action = if self.role == Role::Racer
+                && self.boost_available
+                && parameters.config.racer.opening_boost
+            {
+                Action::Boost
+            } else {
+                Action::Accelerate(MAX_ACCELERAION)
+            };
+            return (
+                parameters.checkpoints[self.checkpoint_idx],
+                MAX_ACCELERAION,
+                action,
+            );
+        }
+
         let nav_target;
         let rel_vel;
-        match self.role {
-            Role::Racer => {
+        // Sitting in ambush, holding position rather than closing in.
+        let mut waiting = false;
+        let finishing = self.on_final_checkpoint(parameters);
+        let opponent = choose_opponent(self, parameters);
+        // Near the end of the race an attacker that can finish itself is worth
+        // more as a racer than as a blocker, as is one with nobody to block.
+        let role = if finishing || opponent.is_none() {
+            Role::Racer
+        } else {
+            self.role
+        };
+        match (role, opponent) {
+            (Role::Racer, _) | (Role::Attacker, None) => {
                 let current_cp = parameters.checkpoints[self.checkpoint_idx];
-                let next_cp = parameters.checkpoints
-                    [(self.checkpoint_idx + 1) % parameters.checkpoints.len()];
-                nav_target = current_cp + (next_cp - current_cp).normalized() * POD_RADIUS;
+                // There is no next leg to line up for on the finishing checkpoint.
+                nav_target = if finishing {
+                    current_cp
+                } else if parameters.config.racer.arrival_planning {
+                    self.arrival_target(parameters)
+                } else {
+                    parameters.racing_line(self.checkpoint_idx)
+                };
                 rel_vel = -self.vel;
             }
-            Role::Attacker => {
-                let pod = prioritize_opponent(parameters);
-                rel_vel = pod.vel - self.vel;
-                if (pod.pos - self.pos)
+            (Role::Attacker, Some(pod)) => {
+                let lead = predictor.predict(pod, parameters, 1);
+                let lead = if parameters.config.attacker.intercept {
+                    self.intercept(pod, parameters.config.terminal_speed())
+                        .unwrap_or(lead)
+                } else {
+                    lead
+                };
+                if let Some((racer, threat)) = self.goalie_threat(parameters) {
+                    // My racer is worth more than anything the attacker could
+                    // do to the leader, get in the way of whoever is after it.
+                    nav_target = (threat.pos + threat.vel).lerp(racer.pos + racer.vel, 0.5);
+                    rel_vel = threat.vel - self.vel;
+                } else if pod.on_final_checkpoint(parameters) {
+                    // The leader is about to win, blocking further ahead is
+                    // pointless; go straight for it.
+                    nav_target = lead;
+                    rel_vel = pod.vel - self.vel;
+                } else if let Some(ambush) = self.ambush_point(pod, parameters) {
+                    // A pod that cannot thrust is a sitting target if it is
+                    // within reach before it recovers.
+                    let stranded = pod.shield_cooldown > 0
+                        && self.flight_time((pod.pos - self.pos).norm(), &parameters.config)
+                            <= pod.shield_cooldown as f32;
+                    let strike = stranded
+                        || pod.flight_time((ambush - pod.pos).norm(), &parameters.config)
+                            <= parameters.config.attacker.ambush_strike_time;
+                    // Hold the ambush rather than lunge at a pod that would
+                    // only be grazed or missed.
+                    if strike && should_commit_ram(self, pod, parameters) {
+                        nav_target = lead;
+                        rel_vel = pod.vel - self.vel;
+                    } else {
+                        nav_target = ambush;
+                        rel_vel = -self.vel;
+                        waiting = true;
+                    }
+                } else if (pod.pos - self.pos)
                     .normalized()
                     .inner_product(self.vel.normalized())
                     > 0.8
@@ -223,10 +828,13 @@ impl Pod {
                         [(pod.checkpoint_idx + 1) % parameters.checkpoints.len()]
                         - parameters.checkpoints[pod.checkpoint_idx];
                     nav_target = parameters.checkpoints[pod.checkpoint_idx]
-                        + cp_diff.normalized() * (cp_diff.norm() / 2.0);
+                        + parameters.directions[pod.checkpoint_idx] * (cp_diff.norm() / 2.0);
+                    rel_vel = pod.vel - self.vel;
                 } else {
                     let cp_range = parameters.checkpoints[pod.checkpoint_idx] - pod.pos;
-                    nav_target = pod.pos + pod.vel + cp_range.normalized() * POD_RADIUS;
+                    nav_target =
+                        predictor.predict(pod, parameters, 1) + cp_range.normalized() * POD_RADIUS;
+                    rel_vel = pod.vel - self.vel;
                 }
             }
         };
@@ -243,167 +851,3103 @@ impl Pod {
                 acc_norm.normalized() * MAX_ACCELERAION
             };
 
-        let accel = match self.role {
+        let mut accel = match role {
             Role::Racer => {
-                if self.flight_time(range.norm()) < FUTURE_TIME {
-                    steer_vec = parameters.checkpoints
-                        [(self.checkpoint_idx + 1) % parameters.checkpoints.len()];
+                let mut aim = range;
+                let next_cp = parameters.checkpoints
+                    [(self.checkpoint_idx + 1) % parameters.checkpoints.len()];
+                if !finishing && parameters.config.racer.arrival_planning {
+                    // The arrival target was picked for a pod heading straight
+                    // for it.
+                    steer_vec = nav_target;
+                } else if !finishing {
+                    // Start curving towards the next leg while closing in on the
+                    // checkpoint, gradually rather than snapping over to it,
+                    // until the pod is about to be through anyway.
+                    let current_cp = parameters.checkpoints[self.checkpoint_idx];
+                    let distance = (current_cp - self.pos).norm();
+                    let lookahead = self.lookahead(current_cp, next_cp, &parameters.config);
+                    let blend = (1.0 - distance / (CHECKPOINT_RADIUS + lookahead)).clamp(0.0, 1.0);
+                    if self.flight_time(range.norm(), &parameters.config)
+                        < self.switch_time(&parameters.config)
+                    {
+                        steer_vec = next_cp;
+                        aim = next_cp - self.pos;
+                    } else if blend > 0.0 {
+                        let heading = (steer_vec - self.pos)
+                            .normalized()
+                            .lerp((next_cp - self.pos).normalized(), blend);
+                        steer_vec = self.pos + heading.normalized() * MAX_ACCELERAION;
+                        aim = next_cp - self.pos;
+                    }
+                }
+                // Coasting alone would carry the pod this far towards its aim,
+                // any thrust beyond that only overshoots.
+                let stopping = self.stopping_distance(&parameters.config)
+                    * self
+                        .vel
+                        .normalized()
+                        .inner_product(aim.normalized())
+                        .max(0.0);
+                let overshoot = if stopping > aim.norm() {
+                    aim.norm() / stopping
+                } else {
+                    1.0
+                };
+                let alignment = self.alignment_to(steer_vec);
+                if alignment < parameters.config.racer.realign_alignment {
+                    // Thrust this far off target mostly pushes the wrong way,
+                    // spend the turn rotating on the spot instead.
+                    0.0
+                } else {
+                    thrust_profile(alignment, role, &parameters.config) * overshoot
                 }
-                (self
-                    .orientation
-                    .inner_product((steer_vec - self.pos).normalized())
-                    .powi(4)
-                    * 16.0)
-                    .tanh()
-                    * MAX_ACCELERAION
             }
             Role::Attacker => {
-                (self
-                    .orientation
-                    .inner_product((steer_vec - self.pos).normalized())
-                    .powi(4)
-                    * 16.0)
-                    .tanh()
-                    * MAX_ACCELERAION
+                let mut creep = 1.0;
+                if waiting {
+                    // Drift into the ambush point rather than overshooting it,
+                    // and once there turn to face the incoming opponent.
+                    let stopping = self.stopping_distance(&parameters.config)
+                        * self
+                            .vel
+                            .normalized()
+                            .inner_product(range.normalized())
+                            .max(0.0);
+                    if let Some(pod) =
+                        opponent.filter(|_| range.norm() < POD_RADIUS && stopping < POD_RADIUS)
+                    {
+                        steer_vec = pod.pos;
+                        creep = 0.0;
+                    } else if stopping > range.norm() {
+                        creep = range.norm() / stopping;
+                    }
+                }
+                let alignment = self.alignment_to(steer_vec);
+                thrust_profile(alignment, role, &parameters.config) * creep
             }
         };
-        self.accel = accel;
 
-        let action = if parameters
+        let nudge = self.wall_nudge(&parameters.config);
+        if nudge != Vec2::default() && nudge.inner_product(steer_vec - self.pos) <= 0.0 {
+            // Steer back in before drifting off the map rather than scrape
+            // along its edge, unless already turning back.
+            let reach = (steer_vec - self.pos).norm();
+            let heading = (steer_vec - self.pos).normalized()
+                + nudge * (parameters.config.wall_avoidance / MAX_ACCELERAION);
+            steer_vec = self.pos + heading.normalized() * reach;
+        }
+
+        if self.oscillating() {
+            // Ease into the new command from the last one instead of flipping
+            // straight over to it.
+            let smoothing = parameters.config.steer_smoothing;
+            let last_idx = (self.history_idx + STEER_HISTORY - 1) % STEER_HISTORY;
+            let heading = self.steer_history[last_idx]
+                .lerp((steer_vec - self.pos).normalized(), smoothing)
+                .normalized();
+            steer_vec = self.pos + heading * MAX_ACCELERAION;
+            accel =
+                self.thrust_history[last_idx] + (accel - self.thrust_history[last_idx]) * smoothing;
+        }
+
+        let threat = parameters
             .opponents
             .iter()
-            .map(|pod| {
-                (
-                    (pod.pos + pod.vel) - (self.pos + self.vel),
-                    self.orientation,
-                )
-            })
-            .any(|(range, orientation)| {
-                (orientation.inner_product(range) > 0.0) && (range.norm() <= POD_RADIUS * 2.2)
-            }) {
-            Action::Shield
+            .map(|pod| self.threat(pod))
+            .fold(0.0, f32::max);
+        let action =
+            if threat - self.shield_cost(accel, parameters) > parameters.config.shield_threshold {
+                Action::Shield
+            } else if role == Role::Racer && self.boost_available && self.boost_ready(parameters) {
+                Action::Boost
+            } else {
+                Action::Accelerate(accel)
+            };
+        (steer_vec, accel, action)
+    }
+
+    fn motion(&self) -> Motion {
+        Motion {
+            pos: self.pos,
+            vel: self.vel,
+            orientation: self.orientation,
+        }
+    }
+
+    /// How much the velocity changed over the last frame, thrust and friction
+    /// together.
+    fn velocity_change(&self) -> Vec2 {
+        self.vel - self.prev.vel
+    }
+
+    /// Degrees turned over the last frame, positive clockwise on the map like
+    /// the game's angles.
+    fn turn_rate(&self) -> f32 {
+        self.prev
+            .orientation
+            .outer_product(self.orientation)
+            .atan2(self.prev.orientation.inner_product(self.orientation))
+            .to_degrees()
+    }
+
+    /// Estimate an opponent's `shield_cooldown` and `mass` after an `update`.
+    /// Raising a shield cuts thrust for the turn, so a pod that was moving
+    /// briskly but ends up exactly where friction alone would leave its
+    /// velocity most likely shielded.
+    fn infer_shield(&mut self, config: &Config) {
+        let coasted = self.prev.vel * config.friction;
+        let coasted = Vec2::new(coasted.x.trunc(), coasted.y.trunc());
+        self.shield_cooldown = if self.shield_cooldown > 0 {
+            self.shield_cooldown - 1
+        } else if self.prev.vel.norm() > MAX_ACCELERAION && (self.vel - coasted).norm() < 1.0 {
+            SHIELD_COOLDOWN
         } else {
-            Action::Accelerate(accel)
+            0
+        };
+        self.mass = if self.shield_cooldown == SHIELD_COOLDOWN {
+            SHIELD_MASS
+        } else {
+            POD_MASS
         };
-        (steer_vec, action)
     }
 
-    /// Approximate time it will take to travel `distance` assuming current
-    /// thrust with no direction change.
-    fn flight_time(&self, distance: f32) -> f32 {
-        // This will never be smaller than 1.0.
-        let accel = self.accel.max(1.0);
-        distance * (1.0 - DRAG_COEF) / DRAG_COEF / accel - self.vel.norm() / accel
-            + DRAG_COEF / (1.0 - DRAG_COEF)
+    /// What raising a shield now would cost in `threat` terms: the `accel`
+    /// given up over the cooldown, weighted by `Config::shield_cost`. Thrust
+    /// matters less the more of the way to the checkpoint the pod would coast
+    /// anyway.
+    fn shield_cost(&self, accel: f32, parameters: &RaceParameters) -> f32 {
+        let distance = (parameters.checkpoints[self.checkpoint_idx] - self.pos).norm();
+        let coasting = (self.stopping_distance(&parameters.config) / distance.max(1.0)).min(1.0);
+        accel * f32::from(SHIELD_COOLDOWN + 1) * (1.0 - coasting) * parameters.config.shield_cost
     }
-}
 
-fn main() {
-    let mut input_line = String::new();
-    io::stdin().read_line(&mut input_line).unwrap();
-    let laps = parse_input!(input_line, u8);
-    input_line.clear();
-    io::stdin().read_line(&mut input_line).unwrap();
-    let checkpoint_n = parse_input!(input_line, usize);
-    let mut checkpoints = Vec::with_capacity(checkpoint_n);
-    for _ in 0..checkpoint_n {
-        input_line.clear();
-        io::stdin().read_line(&mut input_line).unwrap();
-        let inputs = input_line.split(' ').collect::<Vec<_>>();
-        let x = parse_input!(inputs[0], f32);
-        let y = parse_input!(inputs[1], f32);
-        checkpoints.push(Vec2::new(x, y));
+    /// How hard `pod` looks set to hit this one next turn: its closing speed
+    /// times its mass, or 0 if the two will not be touching ahead of this pod.
+    fn threat(&self, pod: &Pod) -> f32 {
+        let range = (pod.pos + pod.vel) - (self.pos + self.vel);
+        if self.orientation.inner_product(range) <= 0.0 || range.norm() > POD_RADIUS * 2.2 {
+            return 0.0;
+        }
+        let closing = (self.vel - pod.vel).inner_product((pod.pos - self.pos).normalized());
+        closing.max(0.0) * pod.mass
     }
 
-    let mut racer = Pod::racer();
-    update_pod(&mut racer);
+    /// Where the pod will have drifted to after `turns` turns without thrust,
+    /// kept on the map.
+    fn predict(&self, turns: u32, config: &Config) -> Vec2 {
+        self.project_position(0.0, turns, config)
+            .clamp_to_map(config.map_width, config.map_height)
+    }
 
-    let mut attacker = Pod::attacker();
-    update_pod(&mut attacker);
+    /// Aim point for the current checkpoint that leaves the pod moving along
+    /// the next leg once through it. Candidates across the checkpoint are
+    /// tried under full thrust and the one arriving with the least heading
+    /// error wins, the plain racing line offset being one of them.
+    fn arrival_target(&self, parameters: &RaceParameters) -> Vec2 {
+        let current_cp = parameters.checkpoints[self.checkpoint_idx];
+        let next_cp =
+            parameters.checkpoints[(self.checkpoint_idx + 1) % parameters.checkpoints.len()];
+        let racing_line = parameters.racing_line(self.checkpoint_idx);
 
-    let opponents: Vec<Pod> = (0..OPPONENTS)
-        .map(|_| {
-            let mut opponent = Pod::racer();
-            update_pod(&mut opponent);
-            opponent
-        })
-        .collect();
-
-    let mut parameters = RaceParameters::new(checkpoints, opponents, laps);
-    println!(
-        "{:.0} {:.0} {}",
-        parameters.checkpoints[racer.checkpoint_idx].x,
-        parameters.checkpoints[racer.checkpoint_idx].y,
-        Action::Boost
-    );
-    println!(
-        "{:.0} {:.0} {}",
-        parameters.checkpoints[racer.checkpoint_idx].x,
-        parameters.checkpoints[racer.checkpoint_idx].y,
-        Action::Accelerate(100.0)
-    );
-    loop {
-        update_pod(&mut racer);
-        update_pod(&mut attacker);
+        // Momentum alone carries the pod well into the checkpoint, so it can
+        // turn for the next leg already.
+        if self.stopping_distance(&parameters.config) >= (current_cp - self.pos).norm()
+            && segment_distance(
+                self.pos,
+                self.predict(ARRIVAL_TURNS, &parameters.config),
+                current_cp,
+            ) < CHECKPOINT_RADIUS / 2.0
+        {
+            return next_cp;
+        }
+
+        let heading_error = |aim: Vec2| {
+            let mut pod = *self;
+            for _ in 0..ARRIVAL_TURNS {
+                let old_pos = pod.pos;
+                pod.step(aim, MAX_ACCELERAION, &parameters.config);
+                if segment_distance(old_pos, pod.pos, current_cp) <= CHECKPOINT_RADIUS {
+                    return Some(
+                        1.0 - pod
+                            .vel
+                            .normalized()
+                            .inner_product((next_cp - pod.pos).normalized()),
+                    );
+                }
+            }
+            None
+        };
+        let side = (current_cp - self.pos).normalized().rotate_deg(90.0);
+        (-ARRIVAL_CANDIDATES..=ARRIVAL_CANDIDATES)
+            .map(|i| current_cp + side * (i as f32 / ARRIVAL_CANDIDATES as f32 * ARRIVAL_SPREAD))
+            .chain([racing_line])
+            .filter_map(|aim| heading_error(aim).map(|error| (aim, error)))
+            .min_by(|(_, error1), (_, error2)| error1.total_cmp(error2))
+            .map_or(racing_line, |(aim, _)| aim)
+    }
+
+    /// How far out from a checkpoint's radius to start turning towards the next
+    /// one. A fast pod needs longer to come round, though only in as much as
+    /// the next leg actually turns away.
+    fn lookahead(&self, current_cp: Vec2, next_cp: Vec2, config: &Config) -> f32 {
+        let turn = (1.0
+            - (current_cp - self.pos)
+                .normalized()
+                .inner_product((next_cp - current_cp).normalized()))
+            / 2.0;
+        config
+            .racer
+            .lookahead_margin
+            .max(self.stopping_distance(config) * turn * config.racer.lookahead_speed_factor)
+    }
+
+    /// Flight time to the checkpoint under which to turn straight for the next
+    /// one, growing with speed from `FUTURE_TIME` at a standstill.
+    fn switch_time(&self, config: &Config) -> f32 {
+        FUTURE_TIME + config.racer.switch_speed_factor * self.vel.norm() / config.terminal_speed()
+    }
+
+    /// Cosine of the angle between where the pod faces and `target`, 1 dead
+    /// ahead and -1 right behind. 0 when `target` is where the pod already is.
+    fn alignment_to(&self, target: Vec2) -> f32 {
+        self.orientation
+            .inner_product((target - self.pos).normalized())
+            .clamp(-1.0, 1.0)
+    }
+
+    /// How far past each edge of the map the pod will be next turn if it
+    /// carries on, pointing back inwards. Zero while it stays on the map.
+    fn wall_nudge(&self, config: &Config) -> Vec2 {
+        let next = self.pos + self.vel;
+        next.clamp_to_map(config.map_width, config.map_height) - next
+    }
+
+    /// Whether recent steering keeps swinging from one side to the other.
+    fn oscillating(&self) -> bool {
+        let headings = (0..STEER_HISTORY)
+            .map(|i| self.steer_history[(self.history_idx + i) % STEER_HISTORY])
+            .collect::<Vec<_>>();
+        let turns = headings
+            .windows(2)
+            .map(|pair| pair[0].outer_product(pair[1]))
+            .collect::<Vec<_>>();
+        turns
+            .windows(2)
+            .filter(|pair| pair[0] * pair[1] < 0.0)
+            .count()
+            >= OSCILLATION_REVERSALS
+    }
+
+    /// Whether the pod is lined up down the longest leg with most of it still
+    /// ahead, where a boost gains the most.
+    fn boost_ready(&self, parameters: &RaceParameters) -> bool {
+        let start = parameters.longest_leg();
+        let end = (start + 1) % parameters.checkpoints.len();
+        let leg = (parameters.checkpoints[end] - parameters.checkpoints[start]).norm();
+        self.checkpoint_idx == end
+            && (parameters.checkpoints[end] - self.pos).norm() > leg / 2.0
+            && self.alignment_to(parameters.checkpoints[end]) > BOOST_ALIGNMENT
+    }
+
+    /// My racer and the opponent set to run into it, provided the racer is
+    /// leading the race clearly enough to be worth guarding.
+    fn goalie_threat<'a>(&self, parameters: &'a RaceParameters) -> Option<(&'a Pod, &'a Pod)> {
+        if !parameters.config.attacker.goalie {
+            return None;
+        }
+        let checkpoint_n = parameters.checkpoints.len();
+        let racer = parameters.team.iter().find(|pod| pod.role == Role::Racer)?;
+        let leading = parameters.opponents.iter().all(|pod| {
+            racer.checkpoints_passed(checkpoint_n)
+                >= pod.checkpoints_passed(checkpoint_n) + GOALIE_LEAD
+        });
+        if !leading {
+            return None;
+        }
         parameters
             .opponents
-            .iter_mut()
-            .for_each(|opponent| update_pod(opponent));
+            .iter()
+            .filter_map(|pod| Some((pod, pod.time_to_collision(racer)?)))
+            .filter(|&(_, time)| time <= TEAMMATE_WARNING_TURNS)
+            .min_by(|(_, time1), (_, time2)| time1.total_cmp(time2))
+            .map(|(pod, _)| (racer, pod))
+    }
 
-        let (racer_steer_vec, racer_action) = racer.navigate(&parameters);
-        let (attacker_steer_vec, attacker_action) = attacker.navigate(&parameters);
+    /// A spot on `pod`'s line of approach just short of its next checkpoint,
+    /// provided this pod is already there or can get there well before it.
+    fn ambush_point(&self, pod: &Pod, parameters: &RaceParameters) -> Option<Vec2> {
+        let checkpoint = parameters.checkpoints[pod.checkpoint_idx];
+        let approach = checkpoint - pod.pos;
+        if approach.norm() <= AMBUSH_DISTANCE {
+            return None;
+        }
+        let ambush = checkpoint - approach.normalized() * AMBUSH_DISTANCE;
+        let in_position = (ambush - self.pos).norm() < POD_RADIUS;
+        let ahead = self.flight_time((ambush - self.pos).norm(), &parameters.config)
+            + parameters.config.attacker.ambush_margin
+            < pod.flight_time((ambush - pod.pos).norm(), &parameters.config);
+        (in_position || ahead).then_some(ambush)
+    }
 
-        println!(
-            "{:.0} {:.0} {}",
-            racer_steer_vec.x.round(),
-            racer_steer_vec.y.round(),
-            racer_action
-        );
-        println!(
-            "{:.0} {:.0} {}",
-            attacker_steer_vec.x.round(),
-            attacker_steer_vec.y.round(),
-            attacker_action
-        );
+    /// Turns until this pod and `pod` touch if both carry on at their current
+    /// velocities, 0 if they already do. `None` if they never will.
+    fn time_to_collision(&self, pod: &Pod) -> Option<f32> {
+        // Solve |offset + closing t| = 2 r for the smallest t that is not past.
+        let offset = pod.pos - self.pos;
+        let closing = pod.vel - self.vel;
+        let contact = 2.0 * POD_RADIUS;
+        let c = offset.inner_product(offset) - contact * contact;
+        if c <= 0.0 {
+            return Some(0.0);
+        }
+        let a = closing.inner_product(closing);
+        let b = 2.0 * offset.inner_product(closing);
+        let discriminant = b * b - 4.0 * a * c;
+        if a < NORMALIZE_EPSILON || b >= 0.0 || discriminant < 0.0 {
+            return None;
+        }
+        Some((-b - discriminant.sqrt()) / (2.0 * a))
     }
-}
 
-fn update_pod(pod: &mut Pod) {
-    let mut input_line = String::new();
-    io::stdin().read_line(&mut input_line).unwrap();
-    let inputs = input_line.split(' ').collect::<Vec<_>>();
-    let x = parse_input!(inputs[0], f32);
-    let y = parse_input!(inputs[1], f32);
-    let vx = parse_input!(inputs[2], f32);
-    let vy = parse_input!(inputs[3], f32);
-    let orient_angle = parse_input!(inputs[4], f32);
-    let checkpoint_idx = parse_input!(inputs[5], usize);
-    pod.update(x, y, vx, vy, orient_angle, checkpoint_idx)
-}
+    /// The earliest point where this pod, moving at `speed`, can meet `pod`
+    /// carrying on at its current velocity. `None` if it cannot catch up.
+    fn intercept(&self, pod: &Pod, speed: f32) -> Option<Vec2> {
+        // Solve |offset + v t| = speed t for the smallest positive t.
+        let offset = pod.pos - self.pos;
+        let a = pod.vel.inner_product(pod.vel) - speed * speed;
+        let b = 2.0 * offset.inner_product(pod.vel);
+        let c = offset.inner_product(offset);
+        let time = if a.abs() < NORMALIZE_EPSILON {
+            -c / b
+        } else {
+            let discriminant = b * b - 4.0 * a * c;
+            if discriminant < 0.0 {
+                return None;
+            }
+            let root = discriminant.sqrt();
+            [(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]
+                .into_iter()
+                .filter(|&time| time >= 0.0)
+                .fold(f32::INFINITY, f32::min)
+        };
+        (time.is_finite() && time >= 0.0).then(|| pod.pos + pod.vel * time)
+    }
 
-fn prioritize_opponent(parameters: &RaceParameters) -> &Pod {
-    let max_lap = parameters
-        .opponents
-        .iter()
-        .map(|pod| pod.lap)
-        .max()
-        .unwrap();
-    let max_checkpoint = parameters
-        .opponents
-        .iter()
-        .filter(|pod| pod.lap == max_lap)
-        .map(|pod| pod.checkpoint_idx)
-        .max()
-        .unwrap();
-    parameters
-        .opponents
-        .iter()
-        .filter(|pod| (pod.lap == max_lap) && (pod.checkpoint_idx == max_checkpoint))
-        .min_by(|pod1, pod2| {
-            (pod1.pos - parameters.checkpoints[max_checkpoint])
-                .norm()
-                .partial_cmp(&(pod2.pos - parameters.checkpoints[max_checkpoint]).norm())
-                .unwrap()
-        })
-        .unwrap()
+    /// Turns this pod needs at full speed to reach where it can meet `pod`.
+    fn intercept_turns(&self, pod: &Pod, config: &Config) -> Option<f32> {
+        let speed = config.terminal_speed();
+        self.intercept(pod, speed)
+            .map(|point| (point - self.pos).norm() / speed)
+    }
+
+    /// Advance the pod by one turn the way the engine does: turn towards
+    /// `target` by at most 18 degrees, thrust, move, then apply friction.
+    /// Velocity is truncated and position floored to whole units afterwards.
+    fn step(&mut self, target: Vec2, thrust: f32, config: &Config) {
+        self.thrust(target, thrust);
+        self.drift(config);
+    }
+
+    /// The steering and acceleration half of `step`.
+    fn thrust(&mut self, target: Vec2, thrust: f32) {
+        let heading = target - self.pos;
+        if heading.norm() > 0.0 {
+            let angle = heading
+                .outer_product(self.orientation)
+                .atan2(heading.inner_product(self.orientation));
+            let max_angle = MAX_ROTATION_DEG / 180.0 * PI;
+            self.orientation = self
+                .orientation
+                .rotate(-angle.clamp(-max_angle, max_angle))
+                .normalized();
+        }
+        self.accel = thrust;
+        self.vel = self.vel + self.orientation * thrust;
+    }
+
+    /// The movement and friction half of `step`.
+    fn drift(&mut self, config: &Config) {
+        self.pos = self.pos + self.vel;
+        self.vel = self.vel * config.friction;
+        self.vel = Vec2::new(self.vel.x.trunc(), self.vel.y.trunc());
+        self.pos = Vec2::new(self.pos.x.floor(), self.pos.y.floor());
+    }
+
+    /// Distance the pod will coast with no more thrust. Every turn it moves by
+    /// its velocity, which friction then shrinks, so this is the sum of a
+    /// geometric series.
+    fn stopping_distance(&self, config: &Config) -> f32 {
+        self.vel.norm() / (1.0 - config.friction)
+    }
+
+    /// Approximate time it will take to travel `distance` assuming current
+    /// thrust with no direction change.
+    fn flight_time(&self, distance: f32, config: &Config) -> f32 {
+        // This will never be smaller than 1.0.
+        let accel = self.accel.max(1.0);
+        let friction = config.friction;
+        distance * (1.0 - friction) / friction / accel - self.vel.norm() / accel
+            + friction / (1.0 - friction)
+    }
+    /// Where `turns` steps of constant `thrust` along the current orientation
+    /// leave the pod, summing the drag series in closed form. The engine's
+    /// rounding is ignored, so over ten turns this lands a couple of dozen
+    /// units ahead of iterating `step`.
+    fn project_position(&self, thrust: f32, turns: u32, config: &Config) -> Vec2 {
+        // Velocity before the k-th move is D^(k-1) v + a (1 - D^k) / (1 - D),
+        // summed over k = 1..=turns.
+        let friction = config.friction;
+        let decay = friction.powi(turns as i32);
+        let coast = (1.0 - decay) / (1.0 - friction);
+        let powered = (turns as f32 - friction * coast) / (1.0 - friction);
+        self.pos + self.vel * coast + self.orientation * (thrust * powered)
+    }
+
+    /// The positions after each of the next `turns` steps holding the same
+    /// `thrust` towards `target`, for drawing where the pod is headed.
+    fn trajectory(&self, thrust: f32, target: Vec2, turns: u32, config: &Config) -> Vec<Vec2> {
+        let mut pod = *self;
+        (0..turns)
+            .map(|_| {
+                pod.step(target, thrust, config);
+                pod.pos
+            })
+            .collect()
+    }
+}
+
+/// A model of where an opponent will be in a few turns, always on the map.
+trait PredictOpponent {
+    fn predict(&self, pod: &Pod, parameters: &RaceParameters, turns: u32) -> Vec2;
+}
+
+/// Carries on at its current velocity.
+struct LinearPrediction;
+
+impl PredictOpponent for LinearPrediction {
+    fn predict(&self, pod: &Pod, parameters: &RaceParameters, turns: u32) -> Vec2 {
+        let config = &parameters.config;
+        (pod.pos + pod.vel * turns as f32).clamp_to_map(config.map_width, config.map_height)
+    }
+}
+
+/// Drives flat out at its checkpoints, friction and turning rate included.
+struct DragPrediction;
+
+impl PredictOpponent for DragPrediction {
+    fn predict(&self, pod: &Pod, parameters: &RaceParameters, turns: u32) -> Vec2 {
+        let mut pod = *pod;
+        for _ in 0..turns {
+            let checkpoint = parameters.checkpoints[pod.checkpoint_idx];
+            let old_pos = pod.pos;
+            pod.step(checkpoint, MAX_ACCELERAION, &parameters.config);
+            // Swept like `Simulator::tick`, a fast pod can jump clean over a
+            // checkpoint between two positions.
+            if segment_distance(old_pos, pod.pos, checkpoint) <= CHECKPOINT_RADIUS {
+                pod.checkpoint_idx = (pod.checkpoint_idx + 1) % parameters.checkpoints.len();
+            }
+        }
+        let config = &parameters.config;
+        pod.pos.clamp_to_map(config.map_width, config.map_height)
+    }
+}
+
+/// Choice of `PredictOpponent` for `Config`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OpponentModel {
+    Linear,
+    Drag,
+}
+
+impl OpponentModel {
+    fn predictor(self) -> &'static dyn PredictOpponent {
+        match self {
+            OpponentModel::Linear => &LinearPrediction,
+            OpponentModel::Drag => &DragPrediction,
+        }
+    }
+}
+
+/// A stripped down re-implementation of the game engine, good enough to play
+/// out races locally and for `search_command` to look ahead with.
+#[derive(Debug, Clone, PartialEq)]
+struct Simulator {
+    checkpoints: Vec<Vec2>,
+    pods: Vec<Pod>,
+    config: Config,
+    // Turns left for each pod to reach its next checkpoint.
+    timeouts: Vec<u32>,
+    // Whether each pod still has its boost. Kept apart from
+    // `Pod::boost_available`, which `Pod::navigate` already clears when it
+    // picks a boost.
+    boosts: Vec<bool>,
+}
+
+impl Simulator {
+    fn new(checkpoints: Vec<Vec2>, pods: Vec<Pod>, config: Config) -> Self {
+        let timeouts = vec![TIMEOUT_TURNS; pods.len()];
+        let boosts = pods.iter().map(|pod| pod.boost_available).collect();
+        Self {
+            checkpoints,
+            pods,
+            config,
+            timeouts,
+            boosts,
+        }
+    }
+
+    /// Play out `turn`, `commands` holding the steering target and action for
+    /// each pod in order.
+    fn tick(&mut self, commands: &[(Vec2, Action)], turn: u32) {
+        let checkpoint_n = self.checkpoints.len();
+        for ((pod, boost), &(target, action)) in
+            self.pods.iter_mut().zip(&mut self.boosts).zip(commands)
+        {
+            let thrust = if pod.shield_cooldown > 0 {
+                pod.shield_cooldown -= 1;
+                0.0
+            } else {
+                match action {
+                    Action::Accelerate(accel) => accel,
+                    Action::Boost if *boost => {
+                        *boost = false;
+                        pod.boost_available = false;
+                        BOOST_ACCELERATION
+                    }
+                    Action::Boost => MAX_ACCELERAION,
+                    Action::Shield => 0.0,
+                }
+            };
+            pod.mass = POD_MASS;
+            if action == Action::Shield {
+                pod.mass = SHIELD_MASS;
+                pod.shield_cooldown = SHIELD_COOLDOWN;
+            }
+            pod.thrust(target, thrust);
+        }
+
+        // Pods that would overlap after moving bounce off each other first.
+        for i in 0..self.pods.len() {
+            for j in (i + 1)..self.pods.len() {
+                let (left, right) = self.pods.split_at_mut(j);
+                let (pod1, pod2) = (&mut left[i], &mut right[0]);
+                let range = (pod2.pos + pod2.vel) - (pod1.pos + pod1.vel);
+                let closing = (pod2.vel - pod1.vel).inner_product(pod2.pos - pod1.pos) < 0.0;
+                if closing && range.norm() < 2.0 * POD_RADIUS {
+                    resolve_collision(pod1, pod2);
+                }
+            }
+        }
+
+        for (pod, timeout) in self.pods.iter_mut().zip(self.timeouts.iter_mut()) {
+            let old_pos = pod.pos;
+            pod.drift(&self.config);
+
+            // Test the whole path travelled this turn, a fast pod can jump clean
+            // over a checkpoint between two positions.
+            let checkpoint = self.checkpoints[pod.checkpoint_idx];
+            if segment_distance(old_pos, pod.pos, checkpoint) <= CHECKPOINT_RADIUS {
+                if pod.checkpoint_idx == 0 {
+                    pod.complete_lap(turn);
+                }
+                pod.checkpoint_idx = (pod.checkpoint_idx + 1) % checkpoint_n;
+                *timeout = TIMEOUT_TURNS;
+            } else {
+                *timeout = timeout.saturating_sub(1);
+            }
+        }
+    }
+}
+
+/// A small xorshift generator, so randomised strategies need no dependencies
+/// and replay exactly from the same seed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // Xorshift never leaves an all zero state.
+        Self(if seed == 0 { DEFAULT_SEED } else { seed })
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Uniform in `[0, 1)`.
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Uniform in `[-amount, amount)`, always 0 without drawing when `amount`
+    /// is 0.
+    fn jitter(&mut self, amount: f32) -> f32 {
+        if amount == 0.0 {
+            0.0
+        } else {
+            (self.next_f32() * 2.0 - 1.0) * amount
+        }
+    }
+}
+
+/// Which commands `search_command` tries and how far ahead it looks.
+#[derive(Debug, Clone, PartialEq)]
+struct SearchConfig {
+    // Steering angles, spread evenly across the turning range either side of
+    // the current orientation.
+    angle_samples: usize,
+    thrust_levels: Vec<f32>,
+    // Also try raising a shield at every angle.
+    shield: bool,
+    depth: usize,
+    beam_width: usize,
+    // Largest random nudge added to each score, to break ties between lines
+    // that look alike. 0 keeps the search deterministic.
+    jitter: f32,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            angle_samples: 5,
+            thrust_levels: vec![0.0, 50.0, 100.0],
+            shield: true,
+            depth: 3,
+            beam_width: 8,
+            jitter: 0.0,
+        }
+    }
+}
+
+impl SearchConfig {
+    /// Every command to try for `pod` this turn, `angle_samples` times the
+    /// thrust levels and shield.
+    fn candidates(&self, pod: &Pod) -> Vec<(Vec2, Action)> {
+        let mut actions: Vec<_> = self
+            .thrust_levels
+            .iter()
+            .map(|&thrust| Action::Accelerate(thrust))
+            .collect();
+        if self.shield {
+            actions.push(Action::Shield);
+        }
+        (0..self.angle_samples)
+            .flat_map(|i| {
+                let angle = if self.angle_samples > 1 {
+                    -MAX_ROTATION_DEG
+                        + 2.0 * MAX_ROTATION_DEG * i as f32 / (self.angle_samples - 1) as f32
+                } else {
+                    0.0
+                };
+                // Far enough out that the pod turns by exactly `angle`.
+                let target = pod.pos + pod.orientation.rotate_deg(angle) * MAP_WIDTH;
+                actions.iter().map(move |&action| (target, action))
+            })
+            .collect()
+    }
+}
+
+/// Beam search over `search`'s candidate commands and whatever `Pod::plan`
+/// would do, scoring where they leave `pod` with `score_state`. Opponents are
+/// assumed to stay where they are. Returns the first command of the best line
+/// found.
+fn search_command(
+    pod: &Pod,
+    parameters: &RaceParameters,
+    search: &SearchConfig,
+    rng: &mut Rng,
+) -> (Vec2, Action) {
+    let mut simulator = Simulator::new(
+        parameters.checkpoints.clone(),
+        vec![*pod],
+        parameters.config,
+    );
+    let mut beam = vec![(*pod, None, f32::NEG_INFINITY)];
+    for turn in (parameters.turn + 1..).take(search.depth) {
+        let mut expanded = Vec::new();
+        for &(state, first, _) in &beam {
+            for command in search
+                .candidates(&state)
+                .into_iter()
+                .chain([state.plan(parameters)])
+            {
+                simulator.pods[0] = state;
+                simulator.tick(&[command], turn);
+                let next = simulator.pods[0];
+                let score = score_state(&[next], parameters) + rng.jitter(search.jitter);
+                expanded.push((next, first.or(Some(command)), score));
+            }
+        }
+        if expanded.is_empty() {
+            break;
+        }
+        expanded.sort_by(|a, b| b.2.total_cmp(&a.2));
+        expanded.truncate(search.beam_width.max(1));
+        beam = expanded;
+    }
+    beam[0].1.unwrap_or((
+        pod.pos + pod.orientation * MAP_WIDTH,
+        Action::Accelerate(0.0),
+    ))
+}
+
+/// Elastic bounce between two touching pods, weighted by their masses. As in
+/// the engine the impulse is applied twice, the second time at least
+/// `MIN_IMPULSE` strong, so even a light touch pushes pods apart.
+fn resolve_collision(pod1: &mut Pod, pod2: &mut Pod) {
+    let normal = pod1.pos - pod2.pos;
+    let normal_sq = normal.inner_product(normal);
+    if normal_sq == 0.0 {
+        return;
+    }
+    let mass_coef = (pod1.mass + pod2.mass) / (pod1.mass * pod2.mass);
+    let product = normal.inner_product(pod1.vel - pod2.vel);
+    let mut impulse = normal * (product / (normal_sq * mass_coef));
+    pod1.vel = pod1.vel - impulse / pod1.mass;
+    pod2.vel = pod2.vel + impulse / pod2.mass;
+
+    if impulse.norm() < MIN_IMPULSE {
+        impulse = impulse.normalized() * MIN_IMPULSE;
+    }
+    pod1.vel = pod1.vel - impulse / pod1.mass;
+    pod2.vel = pod2.vel + impulse / pod2.mass;
+}
+
+/// A wide loop around the map. Three laps take my racer 185 turns in `race`,
+/// and the tests hold it to that.
+#[cfg(test)]
+const OVAL_TRACK: [Vec2; 4] = [
+    Vec2::new(3000.0, 4500.0),
+    Vec2::new(8000.0, 1500.0),
+    Vec2::new(13000.0, 4500.0),
+    Vec2::new(8000.0, 7500.0),
+];
+
+/// Back and forth across the map with a hairpin at every checkpoint. Three
+/// laps take my racer 399 turns in `race`, and the tests hold it to that.
+#[cfg(test)]
+const ZIG_ZAG_TRACK: [Vec2; 6] = [
+    Vec2::new(1500.0, 1500.0),
+    Vec2::new(4500.0, 7500.0),
+    Vec2::new(7500.0, 1500.0),
+    Vec2::new(10500.0, 7500.0),
+    Vec2::new(13500.0, 1500.0),
+    Vec2::new(14500.0, 7500.0),
+];
+
+/// Play out a race between my racer, steered with `config`, and an opponent
+/// that just drives flat out at its checkpoints. Returns the turn my racer
+/// finished on, or `None` if it timed out or took longer than `max_turns`.
+#[cfg(test)]
+fn race(checkpoints: &[Vec2], laps: u8, max_turns: u32, config: Config) -> Option<u32> {
+    let start = checkpoints[0];
+    let side = (checkpoints[1] - start).normalized().rotate_deg(90.0) * (2.0 * POD_RADIUS);
+    let mut racer = Pod::racer();
+    racer.pos = start + side;
+    let mut opponent = Pod::racer();
+    opponent.pos = start - side;
+    let mut simulator = Simulator::new(
+        checkpoints.to_vec(),
+        vec![racer, opponent],
+        Config::default(),
+    );
+    let mut parameters = RaceParameters::new(checkpoints.to_vec(), vec![opponent], laps, config);
+
+    for turn in 1..=max_turns {
+        parameters.turn = turn;
+        parameters.opponents[0] = simulator.pods[1];
+        let command = simulator.pods[0].navigate(&parameters);
+        let opponent = &simulator.pods[1];
+        let opponent_command = (
+            simulator.checkpoints[opponent.checkpoint_idx],
+            Action::Accelerate(MAX_ACCELERAION),
+        );
+        simulator.tick(&[command, opponent_command], turn);
+
+        if simulator.pods[0].lap >= laps {
+            return Some(turn);
+        }
+        if simulator.timeouts[0] == 0 {
+            return None;
+        }
+    }
+    None
+}
+
+/// How a `self_play` match ended.
+#[cfg(test)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MatchResult {
+    /// The team that finished first, or the one left after the other timed
+    /// out. `None` if `max_turns` ran out first.
+    winner: Option<usize>,
+    /// The turn the match was decided on.
+    turns: u32,
+    /// The most checkpoints passed by a pod of each team.
+    progress: [usize; 2],
+}
+
+/// Play my bot against itself, team 0 steered with `configs[0]` and team 1
+/// with `configs[1]`, each fielding its config's `pods`. Both teams line up
+/// across the first leg and race with
+/// collisions through `Simulator`, so the same inputs always give the same
+/// result.
+#[cfg(test)]
+fn self_play(checkpoints: &[Vec2], laps: u8, max_turns: u32, configs: [Config; 2]) -> MatchResult {
+    let start = checkpoints[0];
+    let side = (checkpoints[1] - start).normalized().rotate_deg(90.0) * (2.0 * POD_RADIUS);
+    let sizes = configs.map(|config| config.pods);
+    let teams = [0..sizes[0], sizes[0]..sizes[0] + sizes[1]];
+    let mut pods = team(sizes[0]);
+    pods.extend(team(sizes[1]));
+    let lined_up = pods.len();
+    for (i, pod) in pods.iter_mut().enumerate() {
+        pod.pos = start + side * (i as f32 - (lined_up as f32 - 1.0) / 2.0);
+    }
+    let mut simulator = Simulator::new(checkpoints.to_vec(), pods, Config::default());
+    let mut parameters =
+        configs.map(|config| RaceParameters::new(checkpoints.to_vec(), Vec::new(), laps, config));
+    let checkpoint_n = checkpoints.len();
+    let progress = |simulator: &Simulator| {
+        [0, 1].map(|team| {
+            simulator.pods[teams[team].clone()]
+                .iter()
+                .map(|pod| pod.checkpoints_passed(checkpoint_n))
+                .max()
+                .unwrap_or(0)
+        })
+    };
+
+    for turn in 1..=max_turns {
+        for parameters in &mut parameters {
+            parameters.turn = turn;
+        }
+        parameters[0].opponents = simulator.pods[teams[1].clone()].to_vec();
+        parameters[0].team = simulator.pods[teams[0].clone()].to_vec();
+        parameters[1].opponents = simulator.pods[teams[0].clone()].to_vec();
+        parameters[1].team = simulator.pods[teams[1].clone()].to_vec();
+        let mut commands: Vec<_> = simulator
+            .pods
+            .iter_mut()
+            .enumerate()
+            .map(|(i, pod)| pod.navigate(&parameters[usize::from(i >= sizes[0])]))
+            .collect();
+        for (team, parameters) in parameters.iter().enumerate() {
+            let team = teams[team].clone();
+            coordinate(
+                &mut simulator.pods[team.clone()],
+                &mut commands[team],
+                parameters,
+            );
+        }
+        simulator.tick(&commands, turn);
+
+        let finished = [0, 1].map(|team| {
+            simulator.pods[teams[team].clone()]
+                .iter()
+                .any(|pod| pod.lap >= laps)
+        });
+        // A team is out once none of its pods has passed a checkpoint in time.
+        let timed_out = [0, 1].map(|team| {
+            simulator.timeouts[teams[team].clone()]
+                .iter()
+                .all(|&timeout| timeout == 0)
+        });
+        let winner = match (finished, timed_out) {
+            ([true, _], _) => Some(0),
+            ([false, true], _) => Some(1),
+            (_, [true, false]) => Some(1),
+            (_, [false, true]) => Some(0),
+            // Both out on the same turn, call it on progress.
+            (_, [true, true]) => {
+                let progress = progress(&simulator);
+                Some(if progress[1] > progress[0] { 1 } else { 0 })
+            }
+            _ => None,
+        };
+        if winner.is_some() {
+            return MatchResult {
+                winner,
+                turns: turn,
+                progress: progress(&simulator),
+            };
+        }
+    }
+    MatchResult {
+        winner: None,
+        turns: max_turns,
+        progress: progress(&simulator),
+    }
+}
+
+/// Shortest distance from `point` to the segment between `start` and `end`.
+fn segment_distance(start: Vec2, end: Vec2, point: Vec2) -> f32 {
+    let segment = end - start;
+    let length_sq = segment.inner_product(segment);
+    if length_sq == 0.0 {
+        return (point - start).norm();
+    }
+    let t = ((point - start).inner_product(segment) / length_sq).clamp(0.0, 1.0);
+    (point - (start + segment * t)).norm()
+}
+
+/// One pod's line of turn input.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PodFrame {
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+    orient_angle: f32,
+    checkpoint_idx: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ParseError {
+    MissingField(&'static str),
+    InvalidField { field: &'static str, value: String },
+    OutOfBounds { field: &'static str, value: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::MissingField(field) => write!(f, "missing field `{field}`"),
+            ParseError::InvalidField { field, value } => {
+                write!(f, "field `{field}` has unparsable value `{value}`")
+            }
+            ParseError::OutOfBounds { field, value } => {
+                write!(f, "field `{field}` is out of bounds with value `{value}`")
+            }
+        }
+    }
+}
+
+/// Column of each field in a pod line. Columns not listed here are ignored,
+/// so protocol variants with extra fields still parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PodLayout {
+    x: usize,
+    y: usize,
+    vx: usize,
+    vy: usize,
+    angle: usize,
+    next_checkpoint_id: usize,
+}
+
+impl Default for PodLayout {
+    /// The order documented in the game statement.
+    fn default() -> Self {
+        Self {
+            x: 0,
+            y: 1,
+            vx: 2,
+            vy: 3,
+            angle: 4,
+            next_checkpoint_id: 5,
+        }
+    }
+}
+
+impl PodFrame {
+    /// Parse a pod line in the documented layout.
+    fn parse(line: &str, checkpoint_n: usize) -> Result<Self, ParseError> {
+        Self::parse_with(line, &PodLayout::default(), checkpoint_n)
+    }
+
+    /// Parse a pod line, checking that the position is on the map give or take
+    /// `MAP_SLACK`, as collisions can push a pod past the edge, that the angle
+    /// is a heading (or -1 before the first move) and the checkpoint exists.
+    fn parse_with(line: &str, layout: &PodLayout, checkpoint_n: usize) -> Result<Self, ParseError> {
+        let inputs = line.split_whitespace().collect::<Vec<_>>();
+        let column = |idx: usize| inputs.get(idx).copied();
+        let x: f32 = parse_field(column(layout.x), "x")?;
+        let y: f32 = parse_field(column(layout.y), "y")?;
+        let vx: f32 = parse_field(column(layout.vx), "vx")?;
+        let vy: f32 = parse_field(column(layout.vy), "vy")?;
+        let orient_angle: f32 = parse_field(column(layout.angle), "angle")?;
+        let checkpoint_idx: usize =
+            parse_field(column(layout.next_checkpoint_id), "nextCheckPointId")?;
+        // Ranges are false for NaN, so these also reject positions that are
+        // not numbers at all.
+        check_bounds("x", x, (-MAP_SLACK..=MAP_WIDTH + MAP_SLACK).contains(&x))?;
+        check_bounds("y", y, (-MAP_SLACK..=MAP_HEIGHT + MAP_SLACK).contains(&y))?;
+        check_bounds("vx", vx, vx.is_finite())?;
+        check_bounds("vy", vy, vy.is_finite())?;
+        check_bounds(
+            "angle",
+            orient_angle,
+            orient_angle == -1.0 || (0.0..360.0).contains(&orient_angle),
+        )?;
+        check_bounds(
+            "nextCheckPointId",
+            checkpoint_idx,
+            checkpoint_idx < checkpoint_n,
+        )?;
+        Ok(Self {
+            x,
+            y,
+            vx,
+            vy,
+            orient_angle,
+            checkpoint_idx,
+        })
+    }
+}
+
+fn parse_field<T: std::str::FromStr>(
+    input: Option<&str>,
+    field: &'static str,
+) -> Result<T, ParseError> {
+    let input = input.ok_or(ParseError::MissingField(field))?;
+    input.parse().map_err(|_| ParseError::InvalidField {
+        field,
+        value: input.to_string(),
+    })
+}
+
+fn check_bounds(
+    field: &'static str,
+    value: impl fmt::Display,
+    valid: bool,
+) -> Result<(), ParseError> {
+    if valid {
+        Ok(())
+    } else {
+        Err(ParseError::OutOfBounds {
+            field,
+            value: value.to_string(),
+        })
+    }
+}
+
+/// The game's turn protocol on top of a line based transport, so the game loop
+/// can be driven by something other than stdin and stdout.
+trait GameIo {
+    /// The next line of input, `None` once it has been closed.
+    fn read_line(&mut self) -> Option<String>;
+
+    fn write_line(&mut self, line: &str);
+
+    /// Read the lap count and checkpoints sent once before the first turn.
+    /// `None` if the input closes early or does not parse.
+    fn read_race(&mut self) -> Option<(u8, Vec<Vec2>)> {
+        let laps = self.read_line()?.trim().parse().ok()?;
+        let checkpoint_n = self.read_line()?.trim().parse().ok()?;
+        let checkpoints = (0..checkpoint_n)
+            .map(|_| {
+                let input_line = self.read_line()?;
+                let inputs = input_line.split_whitespace().collect::<Vec<_>>();
+                let x = inputs.first()?.parse().ok()?;
+                let y = inputs.get(1)?.parse().ok()?;
+                Some(Vec2::new(x, y))
+            })
+            .collect::<Option<_>>()?;
+        Some((laps, checkpoints))
+    }
+
+    /// Read one pod's line of `turn` into `pod`. A bad line is reported and
+    /// skipped, leaving the pod as it was last turn. `None` if the input has
+    /// been closed.
+    fn read_pod(&mut self, pod: &mut Pod, checkpoint_n: usize, turn: u32) -> Option<()> {
+        let input_line = self.read_line()?;
+        match PodFrame::parse(&input_line, checkpoint_n) {
+            Ok(frame) => pod.update(&frame, turn),
+            Err(err) => eprintln!("ignoring pod input `{}`: {err}", input_line.trim()),
+        }
+        Some(())
+    }
+
+    /// Write one pod's command, thrust kept within what the referee accepts.
+    fn write_command(&mut self, steer_vec: Vec2, action: Action) {
+        self.write_line(&format!(
+            "{:.0} {:.0} {}",
+            steer_vec.x.round(),
+            steer_vec.y.round(),
+            action.clamped()
+        ));
+    }
+}
+
+/// The CodinGame referee's side of the protocol.
+struct StdIo;
+
+impl GameIo for StdIo {
+    fn read_line(&mut self) -> Option<String> {
+        let mut input_line = String::new();
+        match io::stdin().read_line(&mut input_line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(input_line),
+        }
+    }
+
+    fn write_line(&mut self, line: &str) {
+        println!("{line}");
+    }
+}
+
+fn main() {
+    // `--once` plays a single turn and prints the decisions as JSON, for
+    // driving the bot from other tools.
+    let once = env::args().any(|arg| arg == "--once");
+    run(&mut StdIo, once);
+}
+
+fn run(game_io: &mut impl GameIo, once: bool) {
+    // Debug output goes to stderr so it never ends up in the commands read by
+    // the referee.
+    let logging = env::var("POD_LOG").is_ok_and(|value| value == "1");
+    // `POD_SEARCH=1` has every pod search ahead through the simulator
+    // instead of steering by the rules of `Pod::navigate`.
+    let searching = env::var("POD_SEARCH").is_ok_and(|value| value == "1");
+    // `POD_PREDICTION=drag` has attackers expect opponents to drive at their
+    // checkpoints rather than carry straight on.
+    let drag = env::var("POD_PREDICTION").is_ok_and(|value| value == "drag");
+    // The referee closes the input once the game is over, which ends the bot.
+    let Some((laps, checkpoints)) = game_io.read_race() else {
+        return;
+    };
+
+    let mut config = Config::default();
+    if drag {
+        config.attacker.opponent_model = OpponentModel::Drag;
+    }
+    let mut controller = Controller::new(team(config.pods), &config);
+    if searching {
+        controller.search = Some(SearchConfig::default());
+    }
+    let builder = checkpoints
+        .into_iter()
+        .fold(RaceParameters::builder(), RaceParametersBuilder::checkpoint);
+    let builder = (0..config.opponents).fold(builder, |builder, _| builder.opponent(Pod::racer()));
+    let mut parameters = controller
+        .pods
+        .iter()
+        .fold(builder, |builder, &pod| builder.teammate(pod))
+        .laps(laps)
+        .config(config)
+        .build();
+    if logging {
+        if let Some((low, high)) = parameters.bounds() {
+            eprintln!("race: {laps} laps, checkpoints between {low} and {high}");
+        }
+        eprintln!("physics: {:?}", config.physics());
+    }
+    loop {
+        if controller.read_turn(game_io, &mut parameters).is_none() {
+            return;
+        }
+
+        let commands = controller.decide(&parameters);
+
+        let mut decisions = Vec::new();
+        for (i, (pod, &(steer_vec, action))) in controller.pods.iter().zip(&commands).enumerate() {
+            if logging {
+                let path = pod
+                    .trajectory(pod.accel, steer_vec, LOGGED_PATH_TURNS, &config)
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                eprintln!("{i}: {pod} steer={steer_vec} action={action} path={path}");
+            }
+            if once {
+                decisions.push(Decision::new(steer_vec, action).to_json());
+            } else {
+                game_io.write_command(steer_vec, action);
+            }
+        }
+        if once {
+            game_io.write_line(&format!("{{\"decisions\":[{}]}}", decisions.join(",")));
+            return;
+        }
+    }
+}
+
+/// My pods, reading each turn's frame and turning it into one command per
+/// pod.
+struct Controller {
+    pods: Vec<Pod>,
+    // Seeded from `Config::seed`, so a game with the same input replays the
+    // same commands.
+    rng: Rng,
+    // Pick commands with `search_command` rather than `Pod::navigate`.
+    search: Option<SearchConfig>,
+}
+
+impl Controller {
+    fn new(pods: Vec<Pod>, config: &Config) -> Self {
+        Self {
+            pods,
+            rng: Rng::new(config.seed),
+            search: None,
+        }
+    }
+
+    /// Move `parameters` on to the next turn and read its frame: my pods, then
+    /// the opponents, and `parameters.team` from my pods. `None` once the
+    /// input has been closed.
+    fn read_turn(
+        &mut self,
+        game_io: &mut impl GameIo,
+        parameters: &mut RaceParameters,
+    ) -> Option<()> {
+        parameters.turn += 1;
+        let turn = parameters.turn;
+        let checkpoint_n = parameters.checkpoints.len();
+        for pod in &mut self.pods {
+            game_io.read_pod(pod, checkpoint_n, turn)?;
+        }
+        for opponent in &mut parameters.opponents {
+            game_io.read_pod(opponent, checkpoint_n, turn)?;
+            opponent.infer_shield(&parameters.config);
+        }
+        parameters.team.clone_from(&self.pods);
+        Some(())
+    }
+
+    /// Commands for my pods in input order, for the turn last read by
+    /// `read_turn`.
+    fn decide(&mut self, parameters: &RaceParameters) -> Vec<(Vec2, Action)> {
+        let Self { pods, rng, search } = self;
+        let mut commands: Vec<_> = pods
+            .iter_mut()
+            .map(|pod| match search {
+                Some(search) => {
+                    let (steer_vec, action) = search_command(pod, parameters, search, rng);
+                    pod.follow(steer_vec, action);
+                    (steer_vec, action)
+                }
+                None => pod.navigate(parameters),
+            })
+            .collect();
+        coordinate(pods, &mut commands, parameters);
+        commands
+    }
+}
+
+/// One pod's command as printed by `--once`, e.g.
+/// `{"x":8000,"y":4500,"action":"100"}`. `action` is the thrust, `BOOST` or
+/// `SHIELD`, exactly as the referee would be sent it, and the whole turn is
+/// `{"decisions":[...]}` in pod order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Decision {
+    target: Vec2,
+    action: Action,
+}
+
+impl Decision {
+    fn new(target: Vec2, action: Action) -> Self {
+        Self {
+            target,
+            action: action.clamped(),
+        }
+    }
+
+    fn to_json(self) -> String {
+        format!(
+            "{{\"x\":{:.0},\"y\":{:.0},\"action\":\"{}\"}}",
+            self.target.x.round(),
+            self.target.y.round(),
+            self.action
+        )
+    }
+}
+
+/// My pods in input order. The first one races, any others attack, so a lone
+/// pod in 1v1 still goes for the finish.
+fn team(size: usize) -> Vec<Pod> {
+    (0..size)
+        .map(|i| {
+            if i == 0 {
+                Pod::racer()
+            } else {
+                Pod::attacker()
+            }
+        })
+        .collect()
+}
+
+/// The opponent furthest along in the race, if there is any.
+fn prioritize_opponent(parameters: &RaceParameters) -> Option<&Pod> {
+    rank_opponents(parameters)
+        .first()
+        .map(|&idx| &parameters.opponents[idx])
+}
+
+/// The best placed opponent `attacker` can reach within its turn budget, as
+/// there is no point trailing a leader that is out of reach. Falls back to the
+/// closest opponent when none can be reached in time, and `None` when there
+/// are no opponents at all.
+fn choose_opponent<'a>(attacker: &Pod, parameters: &'a RaceParameters) -> Option<&'a Pod> {
+    let opponents = &parameters.opponents;
+    let budget = parameters.config.attacker.reach_turns;
+    rank_opponents(parameters)
+        .into_iter()
+        .map(|idx| &opponents[idx])
+        .find(|pod| {
+            attacker
+                .intercept_turns(pod, &parameters.config)
+                .is_some_and(|turns| turns <= budget)
+        })
+        .or_else(|| {
+            opponents.iter().min_by(|pod1, pod2| {
+                (pod1.pos - attacker.pos)
+                    .norm()
+                    .total_cmp(&(pod2.pos - attacker.pos).norm())
+            })
+        })
+}
+
+/// Keep my own pods from running into each other: when two are about to touch,
+/// the less important one eases off and steers aside. Attackers give way to
+/// racers, and among equals the pod further behind yields, its own record of
+/// the command it sent amended to match.
+fn coordinate(pods: &mut [Pod], commands: &mut [(Vec2, Action)], parameters: &RaceParameters) {
+    let checkpoint_n = parameters.checkpoints.len();
+    let priority = |pod: &Pod| {
+        (
+            pod.role == Role::Racer,
+            pod.checkpoints_passed(checkpoint_n),
+        )
+    };
+    for i in 0..pods.len() {
+        for j in (i + 1)..pods.len() {
+            let Some(time) = pods[i]
+                .time_to_collision(&pods[j])
+                .filter(|&time| time <= TEAMMATE_WARNING_TURNS)
+            else {
+                continue;
+            };
+            let (yielder, other) = if priority(&pods[i]) < priority(&pods[j]) {
+                (i, j)
+            } else {
+                (j, i)
+            };
+            // Push out to the side the yielding pod would pass on anyway,
+            // widening the closest approach.
+            let pod = pods[yielder];
+            let offset = pod.pos - pods[other].pos;
+            let closing = pod.vel - pods[other].vel;
+            let closest = -offset.inner_product(closing) / closing.inner_product(closing).max(1.0);
+            let miss = offset + closing * closest.max(0.0);
+            let away = if miss.norm() < 1.0 {
+                Vec2::new(-closing.y, closing.x).normalized()
+            } else {
+                miss.normalized()
+            };
+            let (target, action) = &mut commands[yielder];
+            let reach = (*target - pod.pos).norm();
+            *target = pod.pos + ((*target - pod.pos).normalized() + away).normalized() * reach;
+            // Easing off the sooner they would touch lets the other pod clear
+            // the crossing first. A boost or shield is kept as it was.
+            if let Action::Accelerate(accel) = action {
+                *accel *= time / TEAMMATE_WARNING_TURNS;
+            }
+            pods[yielder].overrule(*target, *action);
+        }
+    }
+}
+
+/// Whether `attacker` can meet `target` within a few turns and hit it hard
+/// enough to be worth the attempt.
+fn should_commit_ram(attacker: &Pod, target: &Pod, parameters: &RaceParameters) -> bool {
+    let config = &parameters.config.attacker;
+    let Some(turns) = attacker.intercept_turns(target, &parameters.config) else {
+        return false;
+    };
+    let closing =
+        (attacker.vel - target.vel).inner_product((target.pos - attacker.pos).normalized());
+    turns <= config.ram_turns && closing >= config.ram_closing_speed
+}
+
+/// Indices of the opponents, furthest along in the race first. Pods on the
+/// same checkpoint are ordered by how close they are to it.
+fn rank_opponents(parameters: &RaceParameters) -> Vec<usize> {
+    let checkpoint_n = parameters.checkpoints.len();
+    let mut ranking = (0..parameters.opponents.len()).collect::<Vec<_>>();
+    ranking.sort_by(|&idx1, &idx2| {
+        let pod1 = &parameters.opponents[idx1];
+        let pod2 = &parameters.opponents[idx2];
+        pod2.checkpoints_passed(checkpoint_n)
+            .cmp(&pod1.checkpoints_passed(checkpoint_n))
+            .then_with(|| {
+                (pod1.pos - parameters.checkpoints[pod1.checkpoint_idx])
+                    .norm()
+                    .total_cmp(&(pod2.pos - parameters.checkpoints[pod2.checkpoint_idx]).norm())
+            })
+    });
+    ranking
+}
+
+/// Thrust for a pod whose heading is `alignment` (a cosine) off where it is
+/// steering, sharpened by the role's exponent and gain so thrust only tails
+/// off once the pod is well out of line. Nothing at all once it faces away,
+/// even exponents would otherwise drop the sign.
+fn thrust_profile(alignment: f32, role: Role, config: &Config) -> f32 {
+    let (exponent, gain) = match role {
+        Role::Racer => (config.racer.thrust_exponent, config.racer.thrust_gain),
+        Role::Attacker => (config.attacker.thrust_exponent, config.attacker.thrust_gain),
+    };
+    (alignment.max(0.0).powi(exponent) * gain).tanh() * MAX_ACCELERAION
+}
+
+/// How good the race looks for me: progress of my furthest pod, less its
+/// distance to go to the next checkpoint, less how far my attackers are from
+/// the leading opponent's next checkpoint, if there is one.
+fn score_state(my_pods: &[Pod], parameters: &RaceParameters) -> f32 {
+    let config = &parameters.config;
+    let checkpoint_n = parameters.checkpoints.len();
+    let progress = my_pods
+        .iter()
+        .map(|pod| {
+            pod.checkpoints_passed(checkpoint_n) as f32 * config.checkpoint_score
+                - (parameters.checkpoints[pod.checkpoint_idx] - pod.pos).norm()
+                    * config.distance_score
+        })
+        .fold(f32::NEG_INFINITY, f32::max);
+    let positioning: f32 = prioritize_opponent(parameters).map_or(0.0, |leader| {
+        let leader_cp = parameters.checkpoints[leader.checkpoint_idx];
+        my_pods
+            .iter()
+            .filter(|pod| pod.role == Role::Attacker)
+            .map(|pod| (leader_cp - pod.pos).norm() * config.attacker_score)
+            .sum()
+    });
+    progress - positioning
+}
+
+// The bot is submitted to CodinGame as this one file, so there is no library
+// for tests/ to link against, and tests there can only drive the binary over
+// stdin and stdout as tests/replay.rs does. Whole races through `race` and
+// `self_play` play my pods against `Simulator` turn by turn, so they live here
+// with the rest.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRANSCRIPT: &str = include_str!("../tests/fixtures/transcript.txt");
+
+    /// A referee playing back a transcript from memory and keeping every line
+    /// the bot writes.
+    struct Replay<'a> {
+        input: std::str::Lines<'a>,
+        output: Vec<String>,
+    }
+
+    impl<'a> Replay<'a> {
+        fn new(transcript: &'a str) -> Self {
+            Self {
+                input: transcript.lines(),
+                output: Vec::new(),
+            }
+        }
+    }
+
+    impl GameIo for Replay<'_> {
+        fn read_line(&mut self) -> Option<String> {
+            self.input.next().map(|line| format!("{line}\n"))
+        }
+
+        fn write_line(&mut self, line: &str) {
+            self.output.push(line.to_owned());
+        }
+    }
+
+    #[test]
+    fn replay_writes_the_recorded_commands() {
+        let mut replay = Replay::new(TRANSCRIPT);
+        run(&mut replay, false);
+        assert_eq!(
+            replay.output,
+            [
+                "12000 2000 100",
+                "12000 2000 100",
+                "3499 3290 BOOST",
+                "3792 2398 90",
+                "4200 3092 100",
+                "4494 2294 2",
+            ]
+        );
+        let mut again = Replay::new(TRANSCRIPT);
+        run(&mut again, false);
+        assert_eq!(again.output, replay.output);
+    }
+
+    #[test]
+    fn truncated_transcript_ends_the_game_cleanly() {
+        let mut full = Replay::new(TRANSCRIPT);
+        run(&mut full, false);
+        // Cut anywhere, even halfway through a number, the bot just stops.
+        for end in 0..TRANSCRIPT.len() {
+            let mut replay = Replay::new(&TRANSCRIPT[..end]);
+            run(&mut replay, false);
+            assert!(replay.output.len() <= full.output.len());
+        }
+        // Cut between lines, it wrote the same as it would have so far.
+        for end in TRANSCRIPT.match_indices('\n').map(|(idx, _)| idx) {
+            let mut replay = Replay::new(&TRANSCRIPT[..end]);
+            run(&mut replay, false);
+            assert!(
+                full.output.starts_with(&replay.output),
+                "cut at byte {end} wrote {:?}",
+                replay.output
+            );
+        }
+    }
+
+    #[test]
+    fn decide_navigates_then_coordinates() {
+        let mut replay = Replay::new(TRANSCRIPT);
+        let (laps, checkpoints) = replay.read_race().unwrap();
+        let config = Config::default();
+        let opponents = (0..config.opponents).map(|_| Pod::racer()).collect();
+        let mut parameters = RaceParameters::new(checkpoints, opponents, laps, config);
+        let mut controller = Controller::new(team(config.pods), &config);
+        for turn in 1..=3 {
+            controller.read_turn(&mut replay, &mut parameters).unwrap();
+            assert_eq!(parameters.turn, turn);
+            assert_eq!(parameters.team, controller.pods);
+            let mut pods = controller.pods.clone();
+            let mut expected: Vec<_> = pods
+                .iter_mut()
+                .map(|pod| pod.navigate(&parameters))
+                .collect();
+            coordinate(&mut pods, &mut expected, &parameters);
+            assert_eq!(controller.decide(&parameters), expected);
+            assert_eq!(controller.pods, pods);
+        }
+        assert!(controller.read_turn(&mut replay, &mut parameters).is_none());
+    }
+
+    #[test]
+    fn searching_pods_remember_the_command_they_sent() {
+        let mut replay = Replay::new(TRANSCRIPT);
+        let (laps, checkpoints) = replay.read_race().unwrap();
+        let config = Config::default();
+        let opponents = (0..config.opponents).map(|_| Pod::racer()).collect();
+        let mut parameters = RaceParameters::new(checkpoints, opponents, laps, config);
+        let mut controller = Controller::new(team(config.pods), &config);
+        controller.search = Some(SearchConfig::default());
+        for _ in 0..3 {
+            controller.read_turn(&mut replay, &mut parameters).unwrap();
+            let commands = controller.decide(&parameters);
+            for (pod, &(target, action)) in controller.pods.iter().zip(&commands) {
+                let last_idx = (pod.history_idx + STEER_HISTORY - 1) % STEER_HISTORY;
+                assert_eq!(pod.steer_history[last_idx], (target - pod.pos).normalized());
+                if let Action::Accelerate(accel) = action {
+                    assert_eq!(pod.thrust_history[last_idx], accel);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn yielding_pod_remembers_the_command_it_sent() {
+        let mut pods = vec![
+            Pod::new(5000.0, 5000.0, 0.0, 0.0, 0.0, 1, Role::Racer),
+            Pod::new(3600.0, 5000.0, 300.0, 0.0, 0.0, 1, Role::Attacker),
+        ];
+        // Without opponents the attacker races, straight into the back of my
+        // racer.
+        let mut parameters = RaceParameters::builder()
+            .checkpoint(Vec2::new(2000.0, 2000.0))
+            .checkpoint(Vec2::new(12000.0, 5000.0))
+            .build();
+        parameters.turn = 20;
+        let mut commands: Vec<_> = pods
+            .iter_mut()
+            .map(|pod| pod.navigate(&parameters))
+            .collect();
+        let navigated = commands[1];
+        let time = pods[0].time_to_collision(&pods[1]).unwrap();
+        let mut boosting = commands.clone();
+        boosting[1].1 = Action::Boost;
+        coordinate(&mut pods.clone(), &mut boosting, &parameters);
+        coordinate(&mut pods, &mut commands, &parameters);
+        let (target, action) = commands[1];
+        assert_ne!(target, navigated.0);
+        let Action::Accelerate(accel) = navigated.1 else {
+            panic!("navigated to {navigated:?}");
+        };
+        // Two turns off touching, a third of the thrust.
+        let thrust = accel * time / TEAMMATE_WARNING_TURNS;
+        assert!((time - 2.0).abs() < 1e-3, "{time} turns to collision");
+        assert!(thrust > 0.0);
+        assert_eq!(action, Action::Accelerate(thrust));
+        assert_eq!(boosting[1], (target, Action::Boost));
+        let attacker = &pods[1];
+        let last_idx = (attacker.history_idx + STEER_HISTORY - 1) % STEER_HISTORY;
+        assert_eq!(attacker.accel, thrust);
+        assert_eq!(attacker.thrust_history[last_idx], thrust);
+        assert_eq!(
+            attacker.steer_history[last_idx],
+            (target - attacker.pos).normalized()
+        );
+    }
+
+    #[test]
+    fn converging_teammates_steer_clear_of_each_other() {
+        let checkpoints = vec![
+            Vec2::new(2000.0, 2000.0),
+            Vec2::new(12000.0, 5000.0),
+            Vec2::new(6000.0, 8000.0),
+        ];
+        let mut parameters = RaceParameters::builder()
+            .checkpoint(checkpoints[0])
+            .checkpoint(checkpoints[1])
+            .checkpoint(checkpoints[2])
+            .build();
+        let mut racer = Pod::new(5000.0, 5000.0, 300.0, 0.0, 0.0, 1, Role::Racer);
+        racer.boost_available = false;
+        let pods = vec![
+            racer,
+            Pod::new(7000.0, 2200.0, 0.0, 300.0, 90.0, 1, Role::Attacker),
+        ];
+        // Plays the pods on for ten turns and counts the turns they touched
+        // on, telling by whether either ended up anywhere but where it would
+        // have on its own.
+        let collisions = |coordinated: bool, parameters: &mut RaceParameters| {
+            let mut simulator =
+                Simulator::new(checkpoints.clone(), pods.clone(), Config::default());
+            let mut touched = 0;
+            for turn in 20..30 {
+                parameters.turn = turn;
+                parameters.team.clone_from(&simulator.pods);
+                let mut commands: Vec<_> = simulator
+                    .pods
+                    .iter_mut()
+                    .map(|pod| pod.navigate(parameters))
+                    .collect();
+                if coordinated {
+                    coordinate(&mut simulator.pods, &mut commands, parameters);
+                }
+                let alone: Vec<_> = simulator
+                    .pods
+                    .iter()
+                    .zip(&commands)
+                    .map(|(&pod, &command)| {
+                        let mut solo =
+                            Simulator::new(checkpoints.clone(), vec![pod], Config::default());
+                        solo.tick(&[command], turn);
+                        solo.pods[0].pos
+                    })
+                    .collect();
+                simulator.tick(&commands, turn);
+                if simulator
+                    .pods
+                    .iter()
+                    .zip(&alone)
+                    .any(|(pod, &pos)| pod.pos != pos)
+                {
+                    touched += 1;
+                }
+            }
+            touched
+        };
+        assert!(collisions(false, &mut parameters) > 0);
+        assert_eq!(collisions(true, &mut parameters), 0);
+    }
+
+    #[test]
+    fn once_writes_a_single_turn_of_decisions() {
+        let mut replay = Replay::new(TRANSCRIPT);
+        run(&mut replay, true);
+        assert_eq!(
+            replay.output,
+            [concat!(
+                r#"{"decisions":[{"x":12000,"y":2000,"action":"100"},"#,
+                r#"{"x":12000,"y":2000,"action":"100"}]}"#
+            )]
+        );
+    }
+
+    #[test]
+    fn attacker_holds_its_ambush_at_a_standstill() {
+        let opponent = Pod::new(2000.0, 5000.0, 500.0, 0.0, 0.0, 1, Role::Racer);
+        let attacker = Pod::new(8600.0, 5000.0, 0.0, 0.0, 180.0, 1, Role::Attacker);
+        let mut parameters = RaceParameters::builder()
+            .checkpoint(Vec2::new(2000.0, 2000.0))
+            .checkpoint(Vec2::new(10000.0, 5000.0))
+            .checkpoint(Vec2::new(6000.0, 8000.0))
+            .opponent(opponent)
+            .build();
+        parameters.turn = 20;
+        let (_, action) = attacker.plan(&parameters);
+        assert_eq!(action, Action::Accelerate(0.0));
+    }
+
+    #[test]
+    fn attacker_springs_its_ambush_on_the_passing_opponent() {
+        let checkpoints = vec![
+            Vec2::new(2000.0, 2000.0),
+            Vec2::new(10000.0, 5000.0),
+            Vec2::new(6000.0, 8000.0),
+        ];
+        let parameters = |pods: &[Pod], turn| {
+            let builder = checkpoints
+                .iter()
+                .fold(RaceParameters::builder(), |builder, &cp| {
+                    builder.checkpoint(cp)
+                });
+            let mut parameters = pods
+                .iter()
+                .fold(builder, |builder, &pod| builder.opponent(pod))
+                .build();
+            parameters.turn = turn;
+            parameters
+        };
+        // Turn the opponent gets through checkpoint 1 on, and how close it
+        // comes to `ambush` on the way.
+        let run = |pods: Vec<Pod>, ambush: Vec2| {
+            let mut simulator = Simulator::new(checkpoints.clone(), pods, Config::default());
+            let mut closest = f32::INFINITY;
+            for turn in 20..100 {
+                let pods = simulator.pods.clone();
+                let commands = (0..pods.len())
+                    .map(|i| {
+                        let others = [&pods[..i], &pods[i + 1..]].concat();
+                        simulator.pods[i].navigate(&parameters(&others, turn))
+                    })
+                    .collect::<Vec<_>>();
+                simulator.tick(&commands, turn);
+                let opponent = simulator.pods.last().unwrap();
+                closest = closest.min((opponent.pos - ambush).norm());
+                if opponent.checkpoint_idx == 2 {
+                    return (turn, closest);
+                }
+            }
+            panic!("never got through checkpoint 1");
+        };
+        let attacker = Pod::new(7500.0, 5500.0, 0.0, 0.0, 0.0, 1, Role::Attacker);
+        let mut opponent = Pod::new(2000.0, 5000.0, 300.0, 0.0, 0.0, 1, Role::Racer);
+        opponent.boost_available = false;
+        let ambush = attacker
+            .ambush_point(&opponent, &parameters(&[opponent], 20))
+            .expect("can get there well ahead");
+        let (unhindered, _) = run(vec![opponent], ambush);
+        let (ambushed, closest) = run(vec![attacker, opponent], ambush);
+        assert!(closest < POD_RADIUS, "passed {closest} from the ambush");
+        // Knocked aside just short of the checkpoint, it has to come back round.
+        assert!(
+            ambushed >= unhindered + 5,
+            "through on turn {ambushed}, {unhindered} unhindered"
+        );
+    }
+
+    #[test]
+    fn goalie_gets_between_my_racer_and_its_rammer() {
+        let checkpoints = vec![
+            Vec2::new(2000.0, 2000.0),
+            Vec2::new(14000.0, 3000.0),
+            Vec2::new(3000.0, 8000.0),
+        ];
+        // The turn my racer gets through its checkpoint, if it does at all.
+        let run = |goalie: bool| {
+            let mut config = Config::default();
+            config.attacker.goalie = goalie;
+            // My racer a lap clear of the field with an enemy attacker bearing
+            // down on it, while the leader of the rest sits far off the path.
+            let mut racer = Pod::new(5000.0, 3000.0, 450.0, 0.0, 0.0, 1, Role::Racer);
+            racer.lap = 1;
+            let rammer = Pod::new(11500.0, 8000.0, -100.0, -500.0, 259.0, 1, Role::Attacker);
+            let attacker = Pod::new(7500.0, 5000.0, 300.0, -200.0, 326.0, 1, Role::Attacker);
+            let leader = Pod::new(4000.0, 7500.0, 0.0, 0.0, 162.0, 2, Role::Racer);
+            let mut simulator = Simulator::new(
+                checkpoints.clone(),
+                vec![attacker, racer, rammer, leader],
+                Config::default(),
+            );
+            for turn in 20..40 {
+                let [mut attacker, racer, rammer, leader] = [
+                    simulator.pods[0],
+                    simulator.pods[1],
+                    simulator.pods[2],
+                    simulator.pods[3],
+                ];
+                let mut parameters = checkpoints
+                    .iter()
+                    .fold(RaceParameters::builder(), |builder, &cp| {
+                        builder.checkpoint(cp)
+                    })
+                    .teammate(racer)
+                    .opponent(rammer)
+                    .opponent(leader)
+                    .config(config)
+                    .build();
+                parameters.turn = turn;
+                let rammer_target = rammer
+                    .intercept(&racer, parameters.config.terminal_speed())
+                    .unwrap_or(racer.pos);
+                let commands = [
+                    attacker.navigate(&parameters),
+                    (
+                        checkpoints[racer.checkpoint_idx],
+                        Action::Accelerate(MAX_ACCELERAION),
+                    ),
+                    (rammer_target, Action::Accelerate(MAX_ACCELERAION)),
+                    (checkpoints[leader.checkpoint_idx], Action::Accelerate(0.0)),
+                ];
+                simulator.tick(&commands, turn);
+                if simulator.pods[1].checkpoint_idx != racer.checkpoint_idx {
+                    return Some(turn);
+                }
+            }
+            None
+        };
+        // Left to chase the leader the attacker lets the rammer knock my racer
+        // clean off its line, as a goalie it takes the hit instead.
+        assert_eq!(run(false), None);
+        assert!(run(true).is_some());
+    }
+
+    #[test]
+    fn attacker_races_home_from_the_final_checkpoint() {
+        let checkpoints = vec![
+            Vec2::new(2000.0, 2000.0),
+            Vec2::new(10000.0, 5000.0),
+            Vec2::new(6000.0, 8000.0),
+        ];
+        let mut parameters = checkpoints
+            .iter()
+            .fold(RaceParameters::builder(), |builder, &cp| {
+                builder.checkpoint(cp)
+            })
+            .opponent(Pod::new(9000.0, 3000.0, 300.0, 100.0, 20.0, 1, Role::Racer))
+            .build();
+        // On the last lap, one checkpoint short of the finishing one.
+        let mut attacker = Pod::new(7000.0, 7000.0, -200.0, 200.0, 135.0, 2, Role::Attacker);
+        attacker.lap = parameters.laps - 1;
+        let mut simulator = Simulator::new(checkpoints, vec![attacker], Config::default());
+        // The same pod as a racer, to tell whether the attacker plays one.
+        let as_racer = |pod: &Pod| Pod {
+            role: Role::Racer,
+            ..*pod
+        };
+        let (mut attacking, mut racing) = (0, 0);
+        for turn in 20..60 {
+            parameters.turn = turn;
+            let pod = simulator.pods[0];
+            let plan = pod.plan(&parameters);
+            if pod.on_final_checkpoint(&parameters) {
+                assert_eq!(plan, as_racer(&pod).plan(&parameters), "turn {turn}");
+                racing += 1;
+            } else if plan != as_racer(&pod).plan(&parameters) {
+                attacking += 1;
+            }
+            let command = simulator.pods[0].navigate(&parameters);
+            simulator.tick(&[command], turn);
+        }
+        assert!(attacking > 0, "never attacked");
+        assert!(racing > 0, "never reached the final checkpoint");
+    }
+
+    #[test]
+    fn attacker_without_opponents_races() {
+        let attacker = Pod::new(8000.0, 5000.0, 0.0, 0.0, 0.0, 1, Role::Attacker);
+        let mut parameters = RaceParameters::builder()
+            .checkpoint(Vec2::new(2000.0, 2000.0))
+            .checkpoint(Vec2::new(12000.0, 5000.0))
+            .build();
+        parameters.turn = 20;
+        assert!(choose_opponent(&attacker, &parameters).is_none());
+        assert!(prioritize_opponent(&parameters).is_none());
+        let (target, _) = attacker.plan(&parameters);
+        assert!(target.x > attacker.pos.x, "steered for {target}");
+        assert!(score_state(&[attacker], &parameters).is_finite());
+    }
+
+    #[test]
+    fn planning_leaves_the_pod_as_it_was() {
+        let mut parameters = RaceParameters::builder()
+            .checkpoint(Vec2::new(2000.0, 2000.0))
+            .checkpoint(Vec2::new(10000.0, 5000.0))
+            .opponent(Pod::new(6000.0, 4000.0, 200.0, 100.0, 20.0, 1, Role::Racer))
+            .build();
+        parameters.turn = 20;
+        for role in [Role::Racer, Role::Attacker] {
+            let mut pod = Pod::new(5000.0, 3000.0, 300.0, 100.0, 30.0, 1, role);
+            pod.accel = 0.0;
+            let before = pod;
+            let plan = pod.plan(&parameters);
+            assert_eq!(pod, before, "{role:?}");
+            // Navigating still decides the same, only now it is remembered.
+            assert_eq!(pod.navigate(&parameters), plan, "{role:?}");
+            assert_ne!(pod, before, "{role:?}");
+        }
+    }
+
+    #[test]
+    fn racer_eases_off_rather_than_overshoot() {
+        let mut parameters = RaceParameters::builder()
+            .checkpoint(Vec2::new(2000.0, 5000.0))
+            .checkpoint(Vec2::new(12000.0, 5000.0))
+            .build();
+        parameters.turn = 20;
+        // Heading home on the last lap, so the checkpoint is the aim.
+        let heading_home = |x, vx| {
+            let mut pod = Pod::new(x, 5000.0, vx, 0.0, 180.0, 0, Role::Racer);
+            pod.lap = parameters.laps - 1;
+            pod.boost_available = false;
+            pod
+        };
+        // Coasting alone carries this one 4000 units, four times the distance
+        // left.
+        let fast = heading_home(3000.0, -600.0);
+        let slow = heading_home(9000.0, -50.0);
+        assert!(fast.on_final_checkpoint(&parameters));
+        match fast.plan(&parameters).1 {
+            Action::Accelerate(accel) => assert!(accel < 30.0, "fast pod thrust {accel}"),
+            action => panic!("fast pod {action:?}"),
+        }
+        assert_eq!(
+            slow.plan(&parameters).1,
+            Action::Accelerate(MAX_ACCELERAION)
+        );
+    }
+
+    #[test]
+    fn pod_frame_takes_positions_off_the_map() {
+        let frame = PodFrame::parse("-350 9120 12 -40 90 1", 3).unwrap();
+        assert_eq!((frame.x, frame.y), (-350.0, 9120.0));
+        assert_eq!(
+            PodFrame::parse("100 200 0 0 90", 3),
+            Err(ParseError::MissingField("nextCheckPointId"))
+        );
+        assert!(matches!(
+            PodFrame::parse("100 y 0 0 90 1", 3),
+            Err(ParseError::InvalidField { field: "y", .. })
+        ));
+    }
+
+    #[test]
+    fn pod_frame_rejects_malformed_lines() {
+        for (line, field) in [
+            ("NaN 5000 0 0 90 1", "x"),
+            ("5000 inf 0 0 90 1", "y"),
+            ("-9000 5000 0 0 90 1", "x"),
+            ("5000 20000 0 0 90 1", "y"),
+            ("5000 5000 NaN 0 90 1", "vx"),
+            ("5000 5000 0 -inf 90 1", "vy"),
+            ("5000 5000 0 0 360 1", "angle"),
+            ("5000 5000 0 0 90 3", "nextCheckPointId"),
+        ] {
+            assert!(
+                matches!(
+                    PodFrame::parse(line, 3),
+                    Err(ParseError::OutOfBounds { field: bad, .. }) if bad == field
+                ),
+                "`{line}` parsed as {:?}",
+                PodFrame::parse(line, 3)
+            );
+        }
+        assert!(matches!(
+            PodFrame::parse("5000 5000 0 0 90 -1", 3),
+            Err(ParseError::InvalidField {
+                field: "nextCheckPointId",
+                ..
+            })
+        ));
+        assert_eq!(PodFrame::parse("", 3), Err(ParseError::MissingField("x")));
+    }
+
+    #[test]
+    fn pod_frame_follows_a_reordered_layout() {
+        // The checkpoint first, then an extra column to skip, then the rest
+        // with velocity ahead of position.
+        let layout = PodLayout {
+            next_checkpoint_id: 0,
+            vx: 2,
+            vy: 3,
+            x: 4,
+            y: 5,
+            angle: 6,
+        };
+        let frame = PodFrame::parse_with("2 99 -40 12 3100 4200 270", &layout, 3).unwrap();
+        assert_eq!(
+            frame,
+            PodFrame {
+                x: 3100.0,
+                y: 4200.0,
+                vx: -40.0,
+                vy: 12.0,
+                orient_angle: 270.0,
+                checkpoint_idx: 2,
+            }
+        );
+        assert_eq!(
+            PodFrame::parse_with("2 99 -40 12 3100 4200", &layout, 3),
+            Err(ParseError::MissingField("angle"))
+        );
+        // The default layout takes 3100 for the angle and rejects the line.
+        assert!(PodFrame::parse("2 99 -40 12 3100 4200 270", 3).is_err());
+    }
+
+    #[test]
+    fn bad_pod_line_keeps_the_last_state() {
+        let mut replay = Replay::new("4200 3100 150 -20 45 1\nNaN 3000 0 0 0 1\n");
+        let mut pod = Pod::racer();
+        replay.read_pod(&mut pod, 3, 1).unwrap();
+        let before = pod;
+        replay.read_pod(&mut pod, 3, 2).unwrap();
+        assert_eq!(pod, before);
+        assert!(replay.read_pod(&mut pod, 3, 3).is_none());
+    }
+
+    #[test]
+    fn transcript_with_a_bad_line_plays_on() {
+        let mut lines: Vec<_> = TRANSCRIPT.lines().collect();
+        let checkpoint_n: usize = lines[1].trim().parse().unwrap();
+        // The opponents' first line of the second turn, after the race header
+        // and a turn of four pods.
+        lines[2 + checkpoint_n + 4 + 2] = "NaN NaN NaN NaN NaN NaN";
+        let transcript = lines.join("\n");
+        let mut replay = Replay::new(&transcript);
+        run(&mut replay, false);
+        assert_eq!(replay.output.len(), 6);
+    }
+
+    #[test]
+    fn shield_is_saved_for_when_thrust_is_cheap() {
+        let plan = |racer: Pod, opponent: Pod, checkpoints: [Vec2; 2]| {
+            let mut parameters = RaceParameters::builder()
+                .checkpoint(checkpoints[0])
+                .checkpoint(checkpoints[1])
+                .opponent(opponent)
+                .build();
+            parameters.turn = 20;
+            let mut racer = racer;
+            racer.boost_available = false;
+            let cost = racer.shield_cost(MAX_ACCELERAION, &parameters);
+            (racer.threat(&opponent), cost, racer.plan(&parameters).1)
+        };
+        // Flat out down a long straight, closing in on a pod ahead: the three
+        // turns of thrust given up are worth more than the bump avoided.
+        let (straight_threat, straight_cost, straight) = plan(
+            Pod::new(5000.0, 5000.0, 600.0, 0.0, 0.0, 1, Role::Racer),
+            Pod::new(5900.0, 5100.0, 360.0, 0.0, 0.0, 1, Role::Racer),
+            [Vec2::new(2000.0, 2000.0), Vec2::new(14000.0, 5000.0)],
+        );
+        assert!(straight_threat > Config::default().shield_threshold);
+        assert!(straight_cost > 50.0, "cost {straight_cost}");
+        assert!(matches!(straight, Action::Accelerate(_)), "{straight:?}");
+        // Easing into a corner with a rammer bearing down: coasting gets the
+        // pod through anyway, so the shield costs next to nothing.
+        let (corner_threat, corner_cost, corner) = plan(
+            Pod::new(5000.0, 5000.0, 100.0, 0.0, 0.0, 1, Role::Racer),
+            Pod::new(5900.0, 5000.0, -150.0, 0.0, 180.0, 1, Role::Attacker),
+            [Vec2::new(5000.0, 1000.0), Vec2::new(5500.0, 5000.0)],
+        );
+        assert!((corner_threat - straight_threat).abs() < 0.05 * straight_threat);
+        assert_eq!(corner_cost, 0.0);
+        assert_eq!(corner, Action::Shield);
+    }
+
+    #[test]
+    fn inferred_shield_weighs_in_on_the_threat() {
+        let me = Pod::new(5000.0, 5000.0, 0.0, 0.0, 180.0, 1, Role::Racer);
+        let config = Config::default();
+        let mut opponent = Pod::new(4000.0, 5000.0, 500.0, 0.0, 0.0, 1, Role::Racer);
+        let unshielded = me.threat(&opponent);
+        opponent.update(&PodFrame::parse("4425 5000 425 0 0 1", 3).unwrap(), 1);
+        opponent.infer_shield(&config);
+        assert_eq!(opponent.mass, SHIELD_MASS);
+        opponent.vel = Vec2::new(500.0, 0.0);
+        opponent.pos = Vec2::new(4000.0, 5000.0);
+        assert_eq!(me.threat(&opponent), unshielded * SHIELD_MASS);
+        opponent.update(&PodFrame::parse("4900 5000 500 0 0 1", 3).unwrap(), 2);
+        opponent.infer_shield(&config);
+        assert_eq!(opponent.mass, POD_MASS);
+    }
+
+    #[test]
+    fn previous_frame_is_kept_a_frame_behind() {
+        let mut pod = Pod::new(3000.0, 3000.0, 0.0, 0.0, 0.0, 1, Role::Racer);
+        pod.update(&PodFrame::parse("3200 3100 170 85 10 1", 3).unwrap(), 2);
+        pod.update(&PodFrame::parse("3500 3250 244 127 25 1", 3).unwrap(), 3);
+        assert_eq!(pod.prev.pos, Vec2::new(3200.0, 3100.0));
+        assert_eq!(pod.prev.vel, Vec2::new(170.0, 85.0));
+        assert!((pod.prev.orientation.to_degrees() - 10.0).abs() < 1e-3);
+        assert_eq!(pod.velocity_change(), Vec2::new(74.0, 42.0));
+        assert!((pod.turn_rate() - 15.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn laps_are_timed_by_the_turn_they_are_read_on() {
+        let mut pod = Pod::new(3000.0, 3000.0, 0.0, 0.0, 0.0, 0, Role::Racer);
+        pod.update(&PodFrame::parse("3200 3000 200 0 0 1", 3).unwrap(), 42);
+        pod.update(&PodFrame::parse("3400 3000 200 0 0 1", 3).unwrap(), 43);
+        assert_eq!(pod.lap_turns(), [42]);
+        assert_eq!(pod.lap_durations(), [42]);
+    }
+
+    #[test]
+    fn lap_records_stop_at_the_recorded_laps() {
+        let mut pod = Pod::new(3000.0, 3000.0, 0.0, 0.0, 0.0, 1, Role::Racer);
+        let laps = RECORDED_LAPS as u32 + 4;
+        for lap in 1..=laps {
+            // Laps of uneven length, ten turns give or take two.
+            let end = lap * 10 + lap % 3;
+            pod.update(&PodFrame::parse("3000 3000 0 0 0 0", 3).unwrap(), end - 1);
+            pod.update(&PodFrame::parse("3000 3000 0 0 0 1", 3).unwrap(), end);
+            assert_eq!(u32::from(pod.lap), lap);
+            let turns = pod.lap_turns();
+            assert_eq!(turns.len(), (lap as usize).min(RECORDED_LAPS));
+            assert!(turns.windows(2).all(|pair| pair[0] < pair[1]), "{turns:?}");
+            let durations = pod.lap_durations();
+            assert_eq!(durations.len(), turns.len());
+            assert_eq!(durations.iter().sum::<u32>(), *turns.last().unwrap());
+        }
+        // The laps past the record left the recorded ones as they were.
+        let last_recorded = RECORDED_LAPS as u32;
+        assert_eq!(
+            pod.lap_turns().last(),
+            Some(&(last_recorded * 10 + last_recorded % 3))
+        );
+    }
+
+    #[test]
+    fn tiny_vectors_normalize_to_a_unit_or_nothing() {
+        for v in [
+            Vec2::default(),
+            Vec2::new(1e-7, 0.0),
+            Vec2::new(-3e-7, 4e-7),
+            Vec2::new(1e-6, 1e-6),
+            Vec2::new(1e-3, -2e-3),
+            Vec2::new(f32::MIN_POSITIVE, 0.0),
+        ] {
+            let norm = v.normalized().norm();
+            assert!(
+                norm == 0.0 || (norm - 1.0).abs() < 1e-5,
+                "{v:?} normalized to a norm of {norm}"
+            );
+        }
+        assert_eq!(Vec2::new(1e-7, 0.0).normalized(), Vec2::default());
+        assert!((Vec2::new(1e-3, 0.0).normalized() - Vec2::new(1.0, 0.0)).norm() < 1e-6);
+    }
+
+    #[test]
+    fn headings_read_back_as_engine_angles() {
+        // Screen coordinates, so positive y is down and the angles go round
+        // clockwise.
+        for (v, degrees) in [
+            (Vec2::new(1.0, 0.0), 0.0),
+            (Vec2::new(0.0, 1.0), 90.0),
+            (Vec2::new(-1.0, 0.0), 180.0),
+            (Vec2::new(0.0, -1.0), 270.0),
+            (Vec2::new(1.0, -1.0), 315.0),
+        ] {
+            assert!((v.to_degrees() - degrees).abs() < 1e-4, "{v:?}");
+        }
+        for angle in 0..360 {
+            let degrees = Vec2::from_angle((angle as f32).to_radians()).to_degrees();
+            assert!(
+                (0.0..360.0).contains(&degrees),
+                "{angle} read back as {degrees}"
+            );
+            assert!(
+                (degrees - angle as f32).abs() < 1.0,
+                "{angle} read back as {degrees}"
+            );
+        }
+    }
+
+    #[test]
+    fn componentwise_min_and_max() {
+        let a = Vec2::new(3.0, -2.0);
+        let b = Vec2::new(-1.0, 5.0);
+        assert_eq!(a.componentwise_min(b), Vec2::new(-1.0, -2.0));
+        assert_eq!(a.componentwise_max(b), Vec2::new(3.0, 5.0));
+    }
+
+    #[test]
+    fn predictions_stay_on_the_map() {
+        let parameters = RaceParameters::builder()
+            .checkpoint(Vec2::new(8000.0, 4500.0))
+            .checkpoint(Vec2::new(12000.0, 4500.0))
+            .build();
+        let (width, height) = (MAP_WIDTH, MAP_HEIGHT);
+        let mid = Vec2::new(width / 2.0, height / 2.0);
+        // Heading off past each edge and each corner from the middle.
+        for (vel, edge) in [
+            (Vec2::new(-1.0, 0.0), Vec2::new(0.0, mid.y)),
+            (Vec2::new(1.0, 0.0), Vec2::new(width, mid.y)),
+            (Vec2::new(0.0, -1.0), Vec2::new(mid.x, 0.0)),
+            (Vec2::new(0.0, 1.0), Vec2::new(mid.x, height)),
+            (Vec2::new(-1.0, -1.0), Vec2::new(0.0, 0.0)),
+            (Vec2::new(1.0, -1.0), Vec2::new(width, 0.0)),
+            (Vec2::new(-1.0, 1.0), Vec2::new(0.0, height)),
+            (Vec2::new(1.0, 1.0), Vec2::new(width, height)),
+        ] {
+            assert_eq!((mid + vel * width).clamp_to_map(width, height), edge);
+            let pod = Pod::new(
+                mid.x,
+                mid.y,
+                vel.x * 1000.0,
+                vel.y * 1000.0,
+                0.0,
+                1,
+                Role::Racer,
+            );
+            assert_eq!(LinearPrediction.predict(&pod, &parameters, 20), edge);
+            let start = edge - vel * 100.0;
+            let pod = Pod::new(
+                start.x,
+                start.y,
+                vel.x * 1000.0,
+                vel.y * 1000.0,
+                0.0,
+                1,
+                Role::Racer,
+            );
+            let predicted = DragPrediction.predict(&pod, &parameters, 1);
+            assert_eq!(predicted.clamp_to_map(width, height), predicted);
+            if vel.x != 0.0 {
+                assert_eq!(predicted.x, edge.x);
+            }
+            if vel.y != 0.0 {
+                assert_eq!(predicted.y, edge.y);
+            }
+        }
+        assert_eq!(mid.clamp_to_map(width, height), mid);
+    }
+
+    #[test]
+    fn drag_prediction_follows_an_opponent_round_the_bend() {
+        let checkpoints = vec![
+            Vec2::new(2000.0, 2000.0),
+            Vec2::new(9000.0, 2500.0),
+            Vec2::new(9000.0, 7500.0),
+        ];
+        let parameters = checkpoints
+            .iter()
+            .fold(RaceParameters::builder(), |builder, &cp| {
+                builder.checkpoint(cp)
+            })
+            .build();
+        // Fast enough along the first leg to jump clean over the edge of the
+        // checkpoint, never stopping inside it, and then to come round for
+        // the next.
+        let opponent = Pod::new(7400.0, 1950.0, 1000.0, 0.0, 0.0, 1, Role::Racer);
+        let mut simulator = Simulator::new(checkpoints, vec![opponent], Config::default());
+        for turn in 1..=8 {
+            let pod = &simulator.pods[0];
+            let command = (
+                simulator.checkpoints[pod.checkpoint_idx],
+                Action::Accelerate(MAX_ACCELERAION),
+            );
+            simulator.tick(&[command], turn);
+            let actual = simulator.pods[0].pos;
+            assert!((actual - parameters.checkpoints[1]).norm() > CHECKPOINT_RADIUS);
+            let drag = DragPrediction.predict(&opponent, &parameters, turn);
+            let linear = LinearPrediction.predict(&opponent, &parameters, turn);
+            assert_eq!(drag, actual, "turn {turn}");
+            if turn > 1 {
+                assert!((linear - actual).norm() > (drag - actual).norm());
+            }
+        }
+        assert_eq!(simulator.pods[0].checkpoint_idx, 2);
+    }
+
+    #[test]
+    fn cached_directions_match_the_legs() {
+        for checkpoints in [&OVAL_TRACK[..], &ZIG_ZAG_TRACK]
+            .into_iter()
+            .chain(SPARE_TRACKS.map(|(checkpoints, _)| checkpoints))
+        {
+            let parameters =
+                RaceParameters::new(checkpoints.to_vec(), Vec::new(), 3, Config::default());
+            assert_eq!(parameters.directions.len(), checkpoints.len());
+            for (idx, &current_cp) in checkpoints.iter().enumerate() {
+                let next_cp = checkpoints[(idx + 1) % checkpoints.len()];
+                assert_eq!(
+                    parameters.directions[idx],
+                    (next_cp - current_cp).normalized()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn racer_gets_through_checkpoints_almost_on_top_of_each_other() {
+        let checkpoints = [
+            Vec2::new(3000.0, 3000.0),
+            Vec2::new(11000.0, 5000.0),
+            Vec2::new(11300.0, 5200.0),
+            Vec2::new(5000.0, 7000.0),
+        ];
+        let parameters = checkpoints
+            .iter()
+            .fold(RaceParameters::builder(), |builder, &cp| {
+                builder.checkpoint(cp)
+            })
+            .build();
+        // The offset stays short of the close neighbour, well inside the
+        // checkpoint itself.
+        assert!((parameters.racing_line(1) - checkpoints[1]).norm() < CHECKPOINT_RADIUS / 2.0);
+        let turns = race(&checkpoints, 3, 1000, Config::default());
+        assert!(turns.is_some(), "never finished");
+    }
+
+    #[test]
+    fn bounds_hold_every_checkpoint() {
+        let parameters = RaceParameters::builder()
+            .checkpoint(Vec2::new(3000.0, 7000.0))
+            .checkpoint(Vec2::new(12000.0, 2000.0))
+            .checkpoint(Vec2::new(5000.0, 1000.0))
+            .build();
+        assert_eq!(
+            parameters.bounds(),
+            Some((Vec2::new(3000.0, 1000.0), Vec2::new(12000.0, 7000.0)))
+        );
+        assert_eq!(RaceParameters::builder().build().bounds(), None);
+    }
+
+    #[test]
+    fn opponents_rank_by_lap_then_checkpoint_then_distance() {
+        // Both heading for checkpoint 2 on the first lap, one closer to it.
+        let far = Pod::new(3000.0, 3000.0, 0.0, 0.0, 0.0, 2, Role::Racer);
+        let near = Pod::new(5500.0, 7500.0, 0.0, 0.0, 0.0, 2, Role::Racer);
+        // A lap ahead, though only heading for checkpoint 1.
+        let mut lapped = Pod::new(2500.0, 2500.0, 0.0, 0.0, 0.0, 1, Role::Racer);
+        lapped.lap = 1;
+        let parameters = RaceParameters::builder()
+            .checkpoint(Vec2::new(2000.0, 2000.0))
+            .checkpoint(Vec2::new(10000.0, 5000.0))
+            .checkpoint(Vec2::new(6000.0, 8000.0))
+            .opponent(far)
+            .opponent(lapped)
+            .opponent(near)
+            .build();
+        assert_eq!(rank_opponents(&parameters), vec![1, 2, 0]);
+        assert_eq!(prioritize_opponent(&parameters), Some(&lapped));
+    }
+
+    #[test]
+    fn intercept_lies_on_the_target_path() {
+        let attacker = Pod::new(6000.0, 1000.0, 0.0, 0.0, 90.0, 1, Role::Attacker);
+        let target = Pod::new(2000.0, 5000.0, 400.0, 100.0, 0.0, 1, Role::Racer);
+        let speed = Config::default().terminal_speed();
+        let point = attacker
+            .intercept(&target, speed)
+            .expect("fast enough to catch it");
+        // Somewhere ahead along the target's line, reached by both at once.
+        let offset = point - target.pos;
+        assert!(offset.outer_product(target.vel).abs() / target.vel.norm() < 0.1);
+        let time = offset.inner_product(target.vel) / target.vel.inner_product(target.vel);
+        assert!(time > 0.0);
+        assert!(((point - attacker.pos).norm() - speed * time).abs() < 0.5);
+        // Nothing slower than the target can catch it from behind.
+        let behind = Pod::new(0.0, 4500.0, 0.0, 0.0, 0.0, 1, Role::Attacker);
+        assert_eq!(behind.intercept(&target, 300.0), None);
+    }
+
+    #[test]
+    fn attacker_rams_only_what_it_can_hit_hard() {
+        let parameters = RaceParameters::builder()
+            .checkpoint(Vec2::new(2000.0, 5000.0))
+            .checkpoint(Vec2::new(12000.0, 5000.0))
+            .build();
+        let target = Pod::new(6500.0, 5000.0, -200.0, 0.0, 180.0, 1, Role::Racer);
+        // Charging straight at it from close by.
+        let charging = Pod::new(5000.0, 5000.0, 400.0, 0.0, 0.0, 1, Role::Attacker);
+        assert!(should_commit_ram(&charging, &target, &parameters));
+        // Close enough, but drifting alongside rather than into it.
+        let alongside = Pod::new(5000.0, 5000.0, -200.0, 0.0, 180.0, 1, Role::Attacker);
+        assert!(!should_commit_ram(&alongside, &target, &parameters));
+        // Half the map away, it is better off shadowing.
+        let distant = Pod::new(14000.0, 500.0, 0.0, 0.0, 0.0, 1, Role::Attacker);
+        assert!(!should_commit_ram(&distant, &target, &parameters));
+    }
+
+    #[test]
+    fn attacker_targets_the_opponent_it_can_catch() {
+        let attacker = Pod::new(8000.0, 5000.0, 0.0, 0.0, 0.0, 1, Role::Attacker);
+        let leader = Pod::new(1000.0, 8000.0, -500.0, 0.0, 180.0, 2, Role::Racer);
+        let second = Pod::new(9500.0, 5000.0, 100.0, 0.0, 0.0, 1, Role::Racer);
+        let parameters = RaceParameters::builder()
+            .checkpoint(Vec2::new(2000.0, 2000.0))
+            .checkpoint(Vec2::new(12000.0, 5000.0))
+            .checkpoint(Vec2::new(0.0, 8000.0))
+            .opponent(second)
+            .opponent(leader)
+            .build();
+        assert_eq!(prioritize_opponent(&parameters), Some(&leader));
+        assert_eq!(choose_opponent(&attacker, &parameters), Some(&second));
+    }
+
+    #[test]
+    fn step_matches_the_engine_to_the_unit() {
+        let config = Config::default();
+        let mut pod = Pod::new(1000.0, 2000.0, 101.0, -37.0, 45.0, 1, Role::Racer);
+        // Full thrust straight ahead adds 70.71 to each component. Turn one
+        // moves to (1171.71, 2033.71), floored, and leaves a velocity of
+        // (171.71, 33.71) * 0.85 = (145.95, 28.65), truncated.
+        let ahead = |pod: &Pod| pod.pos + pod.orientation * MAP_WIDTH;
+        pod.step(ahead(&pod), MAX_ACCELERAION, &config);
+        assert_eq!(pod.pos, Vec2::new(1171.0, 2033.0));
+        assert_eq!(pod.vel, Vec2::new(145.0, 28.0));
+        // Turn two: (215.71, 98.71) moves to (1386.71, 2131.71) and drags
+        // down to (183.35, 83.90).
+        pod.step(ahead(&pod), MAX_ACCELERAION, &config);
+        assert_eq!(pod.pos, Vec2::new(1386.0, 2131.0));
+        assert_eq!(pod.vel, Vec2::new(183.0, 83.0));
+    }
+
+    #[test]
+    fn shielded_pod_ploughs_on_through_a_collision() {
+        let head_on = |mass| {
+            let mut shielded = Pod::new(5000.0, 5000.0, 300.0, 0.0, 0.0, 1, Role::Attacker);
+            shielded.mass = mass;
+            let mut other = Pod::new(5800.0, 5000.0, -300.0, 0.0, 180.0, 1, Role::Racer);
+            resolve_collision(&mut shielded, &mut other);
+            (shielded.vel, other.vel)
+        };
+        let (light, _) = head_on(POD_MASS);
+        let (heavy, knocked) = head_on(SHIELD_MASS);
+        // Equal pods bounce straight back, a shielded one carries on at most
+        // of its speed and sends the other flying.
+        assert!(light.x < 0.0, "bounced to {light}");
+        assert!(heavy.x > 150.0, "slowed to {heavy}");
+        assert!(knocked.x > 600.0, "knocked to {knocked}");
+    }
+
+    #[test]
+    fn trajectory_ends_where_stepping_does() {
+        let config = Config::default();
+        let pod = Pod::new(5000.0, 5000.0, 200.0, -50.0, 30.0, 1, Role::Racer);
+        let target = Vec2::new(9000.0, 2000.0);
+        let trajectory = pod.trajectory(80.0, target, 6, &config);
+        let mut stepped = pod;
+        for _ in 0..6 {
+            stepped.step(target, 80.0, &config);
+        }
+        assert_eq!(trajectory.len(), 6);
+        assert_eq!(trajectory.last(), Some(&stepped.pos));
+    }
+
+    #[test]
+    fn projection_keeps_up_with_stepping() {
+        let config = Config::default();
+        let pod = Pod::new(3000.0, 4000.0, 250.0, -120.0, 20.0, 1, Role::Racer);
+        for thrust in [0.0, 50.0, MAX_ACCELERAION] {
+            let mut stepped = pod;
+            for turns in 1..=10 {
+                // Straight ahead, so stepping never turns.
+                let target = stepped.pos + stepped.orientation * MAP_WIDTH;
+                stepped.step(target, thrust, &config);
+                let projected = pod.project_position(thrust, turns, &config);
+                let error = (projected - stepped.pos).norm();
+                assert!(error < 30.0, "{error} off after {turns} turns at {thrust}");
+            }
+        }
+        assert_eq!(
+            pod.predict(10, &config),
+            pod.project_position(0.0, 10, &config)
+        );
+    }
+
+    #[test]
+    fn lookahead_grows_with_speed_into_a_turn() {
+        let config = Config::default();
+        let margin = config.racer.lookahead_margin;
+        let current_cp = Vec2::new(10000.0, 5000.0);
+        let hairpin = Vec2::new(4000.0, 5000.0);
+        let straight = Vec2::new(15000.0, 5000.0);
+        let slow = Pod::new(6000.0, 5000.0, 10.0, 0.0, 0.0, 1, Role::Racer);
+        let fast = Pod::new(6000.0, 5000.0, 600.0, 0.0, 0.0, 1, Role::Racer);
+        assert_eq!(slow.lookahead(current_cp, hairpin, &config), margin);
+        assert_eq!(fast.lookahead(current_cp, straight, &config), margin);
+        assert!(fast.lookahead(current_cp, hairpin, &config) > margin);
+    }
+
+    #[test]
+    fn faster_pods_switch_checkpoints_sooner() {
+        let config = Config::default();
+        let standing = Pod::new(6000.0, 5000.0, 0.0, 0.0, 0.0, 1, Role::Racer);
+        let slow = Pod::new(6000.0, 5000.0, 200.0, 0.0, 0.0, 1, Role::Racer);
+        let fast = Pod::new(6000.0, 5000.0, 0.0, -500.0, 0.0, 1, Role::Racer);
+        assert_eq!(standing.switch_time(&config), FUTURE_TIME);
+        assert!(slow.switch_time(&config) > FUTURE_TIME);
+        assert!(fast.switch_time(&config) > slow.switch_time(&config));
+        let mut fixed = config;
+        fixed.racer.switch_speed_factor = 0.0;
+        assert_eq!(fast.switch_time(&fixed), FUTURE_TIME);
+    }
+
+    #[test]
+    fn reset_pod_is_as_good_as_new() {
+        let start = Vec2::new(3000.0, 7000.0);
+        let mut pod = Pod::new(9000.0, 2000.0, 300.0, -120.0, 45.0, 2, Role::Racer);
+        let parameters = RaceParameters::builder()
+            .checkpoint(start)
+            .checkpoint(Vec2::new(12000.0, 2000.0))
+            .checkpoint(Vec2::new(9000.0, 8000.0))
+            .build();
+        pod.navigate(&parameters);
+        pod.update(&PodFrame::parse("9300 1880 255 -102 50 0", 3).unwrap(), 7);
+        pod.lap = 2;
+        pod.boost_available = false;
+        pod.shield_cooldown = 2;
+        pod.mass = SHIELD_MASS;
+        pod.reset(start, Role::Attacker);
+        assert_eq!(
+            pod,
+            Pod::new(start.x, start.y, 0.0, 0.0, 0.0, 1, Role::Attacker)
+        );
+    }
+
+    #[test]
+    fn arrival_target_comes_out_of_a_corner_straighter() {
+        let parameters = RaceParameters::builder()
+            .checkpoint(Vec2::new(2000.0, 5000.0))
+            .checkpoint(Vec2::new(10000.0, 5000.0))
+            .checkpoint(Vec2::new(10000.0, 1000.0))
+            .build();
+        let current_cp = parameters.checkpoints[1];
+        let next_cp = parameters.checkpoints[2];
+        // Heads for the checkpoint aiming wherever `aim` says, and reports how
+        // far off the next leg it is moving once through.
+        let heading_error = |aim: &dyn Fn(&Pod) -> Vec2| {
+            let mut pod = Pod::new(4000.0, 5000.0, 300.0, 0.0, 0.0, 1, Role::Racer);
+            for _ in 0..50 {
+                let old_pos = pod.pos;
+                pod.step(aim(&pod), MAX_ACCELERAION, &parameters.config);
+                if segment_distance(old_pos, pod.pos, current_cp) <= CHECKPOINT_RADIUS {
+                    return 1.0
+                        - pod
+                            .vel
+                            .normalized()
+                            .inner_product((next_cp - pod.pos).normalized());
+                }
+            }
+            panic!("never reached the checkpoint");
+        };
+        let planned = heading_error(&|pod| pod.arrival_target(&parameters));
+        let offset = heading_error(&|_| parameters.racing_line(1));
+        assert!(planned < offset, "planned {planned}, offset {offset}");
+    }
+
+    #[test]
+    fn builder_fills_in_every_field() {
+        let teammate = Pod::new(3000.0, 3000.0, 0.0, 0.0, 0.0, 1, Role::Attacker);
+        let config = Config {
+            friction: 0.5,
+            ..Config::default()
+        };
+        let opponent = Pod::new(9000.0, 3000.0, 0.0, 0.0, 0.0, 1, Role::Racer);
+        let checkpoints = vec![
+            Vec2::new(2000.0, 2000.0),
+            Vec2::new(12000.0, 5000.0),
+            Vec2::new(5000.0, 8000.0),
+        ];
+        let parameters = RaceParameters::builder()
+            .checkpoint(checkpoints[0])
+            .checkpoint(checkpoints[1])
+            .checkpoint(checkpoints[2])
+            .opponent(opponent)
+            .teammate(teammate)
+            .laps(5)
+            .config(config)
+            .build();
+        let mut constructed = RaceParameters::new(checkpoints.clone(), vec![opponent], 5, config);
+        constructed.team = vec![teammate];
+        assert_eq!(parameters, constructed);
+        // The caches worked out by hand: the first leg is the longest.
+        assert_eq!(
+            parameters,
+            RaceParameters {
+                directions: vec![
+                    (checkpoints[1] - checkpoints[0]).normalized(),
+                    (checkpoints[2] - checkpoints[1]).normalized(),
+                    (checkpoints[0] - checkpoints[2]).normalized(),
+                ],
+                longest_leg: 0,
+                checkpoints,
+                opponents: vec![opponent],
+                team: vec![teammate],
+                laps: 5,
+                config,
+                turn: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn simulator_plays_under_its_configs_physics() {
+        let config = Config {
+            friction: 0.5,
+            ..Config::default()
+        };
+        let pod = Pod::new(5000.0, 5000.0, 300.0, 0.0, 0.0, 1, Role::Racer);
+        let mut simulator = Simulator::new(OVAL_TRACK.to_vec(), vec![pod], config);
+        simulator.tick(&[(Vec2::new(9000.0, 5000.0), Action::Accelerate(0.0))], 1);
+        assert_eq!(simulator.pods[0].vel, Vec2::new(150.0, 0.0));
+        assert_eq!(config.physics().friction, 0.5);
+    }
+
+    #[test]
+    fn rng_replays_from_its_seed() {
+        let draws = |seed| {
+            let mut rng = Rng::new(seed);
+            [(); 4].map(|()| rng.next_f32())
+        };
+        assert_eq!(draws(7), draws(7));
+        assert_ne!(draws(7), draws(8));
+        assert!(draws(0).iter().all(|draw| (0.0..1.0).contains(draw)));
+        assert_eq!(Rng::new(7).jitter(0.0), 0.0);
+    }
+
+    #[test]
+    fn same_seed_makes_the_same_decisions() {
+        // Every decision over the transcript, searching with enough jitter
+        // that the draws decide between lines.
+        let decisions = |seed| {
+            let config = Config {
+                seed,
+                ..Config::default()
+            };
+            let mut replay = Replay::new(TRANSCRIPT);
+            let (laps, checkpoints) = replay.read_race().unwrap();
+            let opponents = (0..config.opponents).map(|_| Pod::racer()).collect();
+            let mut parameters = RaceParameters::new(checkpoints, opponents, laps, config);
+            let mut controller = Controller::new(team(config.pods), &config);
+            controller.search = Some(SearchConfig {
+                jitter: 5000.0,
+                ..SearchConfig::default()
+            });
+            let mut decisions = Vec::new();
+            while controller.read_turn(&mut replay, &mut parameters).is_some() {
+                decisions.extend(controller.decide(&parameters));
+            }
+            decisions
+        };
+        assert_eq!(decisions(7), decisions(7));
+        assert_ne!(decisions(7), decisions(8));
+    }
+
+    #[test]
+    fn score_rises_all_the_way_round() {
+        // A hexagon turns gently enough that a pod carried on past a
+        // checkpoint still closes on the next.
+        let centre = Vec2::new(8000.0, 4500.0);
+        let checkpoints: Vec<_> = (0..6)
+            .map(|i| centre + Vec2::from_angle(i as f32 * PI / 3.0) * 3000.0)
+            .collect();
+        let parameters = checkpoints
+            .iter()
+            .fold(RaceParameters::builder(), |builder, &cp| {
+                builder.checkpoint(cp)
+            })
+            .build();
+        let start = checkpoints[0];
+        let pod = Pod::new(start.x, start.y, 0.0, 0.0, 120.0, 1, Role::Racer);
+        let mut simulator = Simulator::new(checkpoints.clone(), vec![pod], Config::default());
+        let mut score = score_state(&simulator.pods, &parameters);
+        let mut jumps = Vec::new();
+        for turn in 1..=300 {
+            let captured = simulator.pods[0].checkpoint_idx;
+            let target = checkpoints[captured];
+            // Crawling, so the distance to go only ever shrinks until the
+            // checkpoint is taken.
+            simulator.tick(&[(target, Action::Accelerate(20.0))], turn);
+            let next = score_state(&simulator.pods, &parameters);
+            assert!(
+                next > score,
+                "score fell from {score} to {next} on turn {turn}"
+            );
+            if simulator.pods[0].checkpoint_idx != captured {
+                jumps.push((captured, next - score));
+            }
+            score = next;
+        }
+        assert!(simulator.pods[0].lap >= 1, "never finished a lap");
+        // Taking checkpoint 0 finishes a lap, and is worth no more than any
+        // other checkpoint for it.
+        let lap_jump = jumps.iter().find(|&&(idx, _)| idx == 0).unwrap().1;
+        let other_jump = jumps.iter().find(|&&(idx, _)| idx != 0).unwrap().1;
+        assert!(
+            (lap_jump - other_jump).abs() < 2.0 * CHECKPOINT_RADIUS,
+            "lap {lap_jump}, checkpoint {other_jump}"
+        );
+    }
+
+    #[test]
+    fn search_tries_every_angle_and_thrust() {
+        let pod = Pod::new(4000.0, 5000.0, 200.0, 0.0, 30.0, 1, Role::Racer);
+        let default = SearchConfig::default();
+        assert_eq!(
+            default.candidates(&pod).len(),
+            default.angle_samples * (default.thrust_levels.len() + 1)
+        );
+        let narrow = SearchConfig {
+            angle_samples: 7,
+            thrust_levels: vec![25.0, 100.0],
+            shield: false,
+            ..SearchConfig::default()
+        };
+        let candidates = narrow.candidates(&pod);
+        assert_eq!(candidates.len(), 7 * 2);
+        assert!(candidates
+            .iter()
+            .all(|(_, action)| *action != Action::Shield));
+        // The outermost angles turn the pod as far as it can go either way.
+        let turned = |target: Vec2| (target - pod.pos).to_degrees();
+        assert!((turned(candidates[0].0) - 12.0).abs() < 0.01);
+        assert!((turned(candidates[candidates.len() - 1].0) - 48.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn search_drives_on_towards_the_checkpoint() {
+        let pod = Pod::new(4000.0, 5000.0, 200.0, 0.0, 0.0, 1, Role::Racer);
+        let parameters = RaceParameters::builder()
+            .checkpoint(Vec2::new(2000.0, 5000.0))
+            .checkpoint(Vec2::new(12000.0, 5000.0))
+            .build();
+        let search = SearchConfig::default();
+        let command = search_command(&pod, &parameters, &search, &mut Rng::new(1));
+        assert_eq!(command.1, Action::Accelerate(100.0));
+        assert_eq!(
+            search_command(&pod, &parameters, &search, &mut Rng::new(1)),
+            command
+        );
+    }
+
+    #[test]
+    fn self_play_is_decided_the_same_way_every_time() {
+        let play = || self_play(&OVAL_TRACK, 3, 600, [Config::default(); 2]);
+        let result = play();
+        assert!(
+            result.winner.is_some(),
+            "undecided after {} turns",
+            result.turns
+        );
+        assert_eq!(play(), result);
+    }
+
+    #[test]
+    fn attacker_profile_leaves_the_racer_alone() {
+        let retuned = Config {
+            attacker: AttackerConfig {
+                thrust_exponent: 1,
+                thrust_gain: 2.0,
+                opponent_model: OpponentModel::Drag,
+                ambush_margin: 0.0,
+                ambush_strike_time: 10.0,
+                intercept: true,
+                ram_turns: 10.0,
+                ram_closing_speed: 0.0,
+                goalie: true,
+                reach_turns: 100.0,
+            },
+            ..Config::default()
+        };
+        let opponent = Pod::new(3000.0, 3800.0, 0.0, 0.0, 0.0, 1, Role::Racer);
+        let mut parameters =
+            RaceParameters::new(OVAL_TRACK.to_vec(), vec![opponent], 3, Config::default());
+        let mut retuned_parameters = RaceParameters {
+            config: retuned,
+            ..parameters.clone()
+        };
+        let racer = Pod::new(3000.0, 2200.0, 0.0, 0.0, 0.0, 1, Role::Racer);
+        let mut simulator = Simulator::new(
+            OVAL_TRACK.to_vec(),
+            vec![racer, opponent],
+            Config::default(),
+        );
+        for turn in 1..=200 {
+            parameters.turn = turn;
+            retuned_parameters.turn = turn;
+            parameters.opponents[0] = simulator.pods[1];
+            retuned_parameters.opponents[0] = simulator.pods[1];
+            let mut twin = simulator.pods[0];
+            let command = simulator.pods[0].navigate(&parameters);
+            assert_eq!(twin.navigate(&retuned_parameters), command, "turn {turn}");
+            let next = simulator.pods[1].checkpoint_idx;
+            let opponent_command = (OVAL_TRACK[next], Action::Accelerate(MAX_ACCELERAION));
+            simulator.tick(&[command, opponent_command], turn);
+        }
+        // Whereas an attacker in the racer's place, not yet racing home,
+        // does take it up.
+        let attacker = Pod {
+            role: Role::Attacker,
+            lap: 0,
+            ..simulator.pods[0]
+        };
+        assert_ne!(
+            attacker.plan(&parameters),
+            attacker.plan(&retuned_parameters)
+        );
+    }
+
+    #[test]
+    fn pod_heading_into_a_corner_turns_back_in_first() {
+        let checkpoints = vec![
+            Vec2::new(8000.0, 1000.0),
+            Vec2::new(12000.0, 7500.0),
+            Vec2::new(2000.0, 8000.0),
+        ];
+        // Furthest the racer ends up past the map edges, with nothing in the
+        // simulator to stop it there.
+        let overrun = |config: Config| {
+            let mut parameters = RaceParameters::new(checkpoints.clone(), Vec::new(), 3, config);
+            // Overshooting its checkpoint, off into the bottom right corner.
+            let mut racer = Pod::new(14253.0, 8432.0, 424.0, 682.0, 349.0, 1, Role::Racer);
+            racer.boost_available = false;
+            let mut simulator = Simulator::new(checkpoints.clone(), vec![racer], config);
+            let mut overrun = 0.0f32;
+            for turn in 20..35 {
+                parameters.turn = turn;
+                let command = simulator.pods[0].navigate(&parameters);
+                simulator.tick(&[command], turn);
+                let pos = simulator.pods[0].pos;
+                overrun = overrun.max((pos.clamp_to_map(MAP_WIDTH, MAP_HEIGHT) - pos).norm());
+            }
+            overrun
+        };
+        let blind = Config {
+            wall_avoidance: 0.0,
+            ..Config::default()
+        };
+        let avoiding = overrun(Config::default());
+        let blind = overrun(blind);
+        // Coming round with the nudge it clears the right edge altogether and
+        // swings back up off the bottom one sooner.
+        assert!(avoiding < blind, "overran by {avoiding}, {blind} blind");
+    }
+
+    #[test]
+    fn alignment_runs_from_ahead_to_behind() {
+        let pod = Pod::new(5000.0, 5000.0, 0.0, 0.0, 90.0, 1, Role::Racer);
+        assert!((pod.alignment_to(Vec2::new(5000.0, 7000.0)) - 1.0).abs() < 1e-6);
+        assert!((pod.alignment_to(Vec2::new(5000.0, 1000.0)) + 1.0).abs() < 1e-6);
+        assert!(pod.alignment_to(Vec2::new(8000.0, 5000.0)).abs() < 1e-6);
+        assert!(pod.alignment_to(Vec2::new(2000.0, 5000.0)).abs() < 1e-6);
+        // Nowhere to point when the target is where the pod is.
+        assert_eq!(pod.alignment_to(pod.pos), 0.0);
+    }
+
+    #[test]
+    fn no_thrust_facing_away() {
+        let config = Config::default();
+        assert_eq!(thrust_profile(-1.0, Role::Attacker, &config), 0.0);
+        assert_eq!(thrust_profile(-1.0, Role::Racer, &config), 0.0);
+        assert!(thrust_profile(1.0, Role::Attacker, &config) > 99.0);
+    }
+
+    #[test]
+    fn attacker_pushes_on_where_the_racer_eases_off() {
+        let config = Config::default();
+        // Some 66 degrees out of line: the racer's fourth power has all
+        // but died away, the attacker's square has not.
+        let racer = thrust_profile(0.4, Role::Racer, &config);
+        let attacker = thrust_profile(0.4, Role::Attacker, &config);
+        assert!((racer - (0.4f32.powi(4) * 16.0).tanh() * 100.0).abs() < 1e-3);
+        assert!((attacker - (0.4f32.powi(2) * 16.0).tanh() * 100.0).abs() < 1e-3);
+        assert!(
+            racer < 40.0 && attacker > 98.0,
+            "racer {racer}, attacker {attacker}"
+        );
+        // Both flat out when dead ahead.
+        assert!(thrust_profile(1.0, Role::Racer, &config) > 99.0);
+        assert!(thrust_profile(1.0, Role::Attacker, &config) > 99.0);
+    }
+
+    /// More layouts beyond the two documented ones, with the turns three laps
+    /// take my racer: the recorded transcript's rectangle, a there and back
+    /// along the middle and a five checkpoint star.
+    const SPARE_TRACKS: [(&[Vec2], u32); 3] = [
+        (
+            &[
+                Vec2::new(3000.0, 3000.0),
+                Vec2::new(12000.0, 2000.0),
+                Vec2::new(13000.0, 7000.0),
+                Vec2::new(4000.0, 7000.0),
+            ],
+            167,
+        ),
+        (
+            &[
+                Vec2::new(8000.0, 4500.0),
+                Vec2::new(14000.0, 4500.0),
+                Vec2::new(9000.0, 5500.0),
+                Vec2::new(2000.0, 4000.0),
+            ],
+            191,
+        ),
+        (
+            &[
+                Vec2::new(5000.0, 5000.0),
+                Vec2::new(11000.0, 1000.0),
+                Vec2::new(7000.0, 8000.0),
+                Vec2::new(2000.0, 2000.0),
+                Vec2::new(14000.0, 7000.0),
+            ],
+            321,
+        ),
+    ];
+
+    #[test]
+    fn stopping_distance_matches_coasting_to_a_halt() {
+        let config = Config::default();
+        for (vx, vy) in [(600.0, 0.0), (-300.0, 400.0), (37.0, -12.0)] {
+            let pod = Pod::new(5000.0, 5000.0, vx, vy, 0.0, 1, Role::Racer);
+            let mut coasting = pod;
+            while coasting.vel != Vec2::default() {
+                coasting.step(coasting.pos + coasting.orientation, 0.0, &config);
+            }
+            // The engine truncates the velocity every turn, which the series
+            // ignores, so the pod comes up a little short of it.
+            let coasted = (coasting.pos - pod.pos).norm();
+            let stopping = pod.stopping_distance(&config);
+            assert!(
+                coasted <= stopping && stopping - coasted < POD_RADIUS / 2.0,
+                "coasted {coasted}, expected {stopping}"
+            );
+        }
+    }
+
+    #[test]
+    fn lower_friction_shortens_the_drift() {
+        let pod = Pod::new(5000.0, 5000.0, 300.0, 0.0, 0.0, 1, Role::Racer);
+        let sticky = Config {
+            friction: 0.5,
+            ..Config::default()
+        };
+        let default = Config::default();
+        assert!(pod.stopping_distance(&sticky) < pod.stopping_distance(&default));
+        assert!(pod.predict(10, &sticky).x < pod.predict(10, &default).x);
+        assert!(sticky.terminal_speed() < default.terminal_speed());
+    }
+
+    #[test]
+    fn racer_laps_the_oval_in_time() {
+        let turns = race(&OVAL_TRACK, 3, 1000, Config::default());
+        assert!(turns.is_some_and(|turns| turns <= 185), "took {turns:?}");
+    }
+
+    #[test]
+    fn racer_laps_the_zig_zag_in_time() {
+        let turns = race(&ZIG_ZAG_TRACK, 3, 1000, Config::default());
+        assert!(turns.is_some_and(|turns| turns <= 399), "took {turns:?}");
+    }
+
+    #[test]
+    fn racer_laps_the_spare_tracks_in_time() {
+        for (checkpoints, limit) in SPARE_TRACKS {
+            let turns = race(checkpoints, 3, 1000, Config::default());
+            assert!(turns.is_some_and(|turns| turns <= limit), "took {turns:?}");
+        }
+    }
+
+    /// Turns three laps of every track take under `config`, a race that
+    /// never finishes counting as 1000.
+    fn total_turns(config: Config) -> u32 {
+        [&OVAL_TRACK[..], &ZIG_ZAG_TRACK]
+            .into_iter()
+            .chain(SPARE_TRACKS.map(|(checkpoints, _)| checkpoints))
+            .map(|checkpoints| race(checkpoints, 3, 1000, config).unwrap_or(1000))
+            .sum()
+    }
+
+    #[test]
+    fn saving_the_boost_beats_boosting_off_the_line() {
+        let mut opening = Config::default();
+        opening.racer.opening_boost = true;
+        let saved = total_turns(Config::default());
+        let spent = total_turns(opening);
+        assert!(saved < spent, "saved {saved} turns, spent {spent}");
+    }
+
+    #[test]
+    fn realigning_on_the_spot_helps_a_gentle_thrust_profile() {
+        let mut gentle = Config::default();
+        gentle.racer.thrust_exponent = 2;
+        gentle.racer.thrust_gain = 4.0;
+        let mut realigning = gentle;
+        realigning.racer.realign_alignment = 0.5;
+        let pushing = total_turns(gentle);
+        let realigning = total_turns(realigning);
+        assert!(
+            realigning < pushing,
+            "realigning took {realigning} turns, pushing on {pushing}"
+        );
+    }
+
+    /// How often the steering my racer sends swings over from one side to the
+    /// other over three laps of `checkpoints` run alone.
+    fn steering_reversals(checkpoints: &[Vec2], config: Config) -> usize {
+        let start = checkpoints[0];
+        let mut racer = Pod::new(start.x, start.y, 0.0, 0.0, 0.0, 1, Role::Racer);
+        racer.boost_available = false;
+        let mut simulator = Simulator::new(checkpoints.to_vec(), vec![racer], Config::default());
+        let mut parameters = RaceParameters::new(checkpoints.to_vec(), Vec::new(), 3, config);
+        let mut headings = Vec::new();
+        for turn in 1..=1000 {
+            parameters.turn = turn;
+            let command = simulator.pods[0].navigate(&parameters);
+            headings.push((command.0 - simulator.pods[0].pos).normalized());
+            simulator.tick(&[command], turn);
+            if simulator.pods[0].lap >= parameters.laps {
+                break;
+            }
+        }
+        let turns = headings
+            .windows(2)
+            .map(|pair| pair[0].outer_product(pair[1]))
+            .collect::<Vec<_>>();
+        turns
+            .windows(2)
+            .filter(|pair| pair[0] * pair[1] < 0.0)
+            .count()
+    }
+
+    #[test]
+    fn smoothing_calms_the_steering_on_a_tight_triangle() {
+        // The short legs and sharp corners here set the unsmoothed racer
+        // chattering from side to side.
+        let triangle = [
+            Vec2::new(6535.0, 3171.0),
+            Vec2::new(4535.0, 6503.0),
+            Vec2::new(7090.0, 7723.0),
+        ];
+        let raw = Config {
+            steer_smoothing: 1.0,
+            ..Config::default()
+        };
+        let chattering = steering_reversals(&triangle, raw);
+        let smoothed = steering_reversals(&triangle, Config::default());
+        assert!(
+            smoothed < chattering,
+            "{smoothed} reversals smoothed, {chattering} without"
+        );
+    }
+
+    #[test]
+    fn blending_into_the_next_leg_beats_snapping_to_it() {
+        // Without any lookahead the blend only starts inside the checkpoint,
+        // leaving the switch to the next one to do all the turning.
+        let mut snapping = Config::default();
+        snapping.racer.lookahead_margin = 0.0;
+        snapping.racer.lookahead_speed_factor = 0.0;
+        let blended = total_turns(Config::default());
+        let snapping = total_turns(snapping);
+        assert!(
+            blended < snapping,
+            "blending took {blended} turns, snapping {snapping}"
+        );
+    }
+
+    #[test]
+    fn looking_further_ahead_at_speed_beats_a_fixed_margin() {
+        let mut fixed = Config::default();
+        fixed.racer.lookahead_speed_factor = 0.0;
+        let scaled = total_turns(Config::default());
+        let fixed = total_turns(fixed);
+        assert!(scaled < fixed, "scaled took {scaled} turns, fixed {fixed}");
+    }
 }
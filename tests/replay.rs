@@ -0,0 +1,41 @@
+//! Replays a recorded referee transcript through the bot and checks that the
+//! commands it prints are always the same, so output diffs between versions
+//! only ever reflect behaviour changes.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const TRANSCRIPT: &str = include_str!("fixtures/transcript.txt");
+
+fn replay(transcript: &str) -> Vec<u8> {
+    let mut bot = Command::new(env!("CARGO_BIN_EXE_mad-pod-racing"))
+        .env_remove("POD_LOG")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("the bot should start");
+    bot.stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(transcript.as_bytes())
+        .expect("the bot should read the whole transcript");
+    let output = bot.wait_with_output().expect("the bot should finish");
+    assert!(
+        output.status.success(),
+        "the bot exited with {}",
+        output.status
+    );
+    output.stdout
+}
+
+#[test]
+fn replay_is_deterministic() {
+    let first = replay(TRANSCRIPT);
+    let second = replay(TRANSCRIPT);
+    assert!(!first.is_empty(), "the bot printed no commands");
+    assert_eq!(
+        first, second,
+        "the same transcript produced different commands"
+    );
+}